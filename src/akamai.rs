@@ -1,6 +1,6 @@
 use serde_derive::Serialize;
 
-use crate::http2::Frame;
+use crate::http2::{Frame, Priority};
 
 #[derive(Clone, Serialize)]
 pub struct Akamai {
@@ -9,7 +9,9 @@ pub struct Akamai {
 }
 
 impl Akamai {
-    pub fn new(frames: &[Frame]) -> Self {
+    /// With `headers_priority`, the priority carried on HEADERS frames is
+    /// listed along with the PRIORITY frames.
+    pub fn new(frames: &[Frame], headers_priority: bool) -> Self {
         let mut settings = Vec::new();
         let mut window_update = "00".to_string();
         let mut priority_frames = Vec::new();
@@ -25,14 +27,12 @@ impl Akamai {
                     window_update = frame.window_size_increment.to_string();
                 }
                 Frame::Priority(frame) => {
-                    let stream_id = frame.stream_id;
-                    let exlusive = frame.priority.exclusive as u8;
-                    let dep_stream_id = frame.priority.dep_stream_id;
-                    let weight = frame.priority.weight;
-                    priority_frames
-                        .push(format!("{stream_id}:{exlusive}:{dep_stream_id}:{weight}"));
+                    priority_frames.push(priority_str(frame.stream_id, &frame.priority));
                 }
                 Frame::Headers(frame) => {
+                    if let (true, Some(priority)) = (headers_priority, &frame.priority) {
+                        priority_frames.push(priority_str(frame.stream_id, priority));
+                    }
                     headers = frame
                         .pseudo_headers
                         .iter()
@@ -66,3 +66,10 @@ impl Akamai {
         }
     }
 }
+
+fn priority_str(stream_id: u32, priority: &Priority) -> String {
+    let exlusive = priority.exclusive as u8;
+    let dep_stream_id = priority.dep_stream_id;
+    let weight = priority.weight;
+    format!("{stream_id}:{exlusive}:{dep_stream_id}:{weight}")
+}
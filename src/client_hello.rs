@@ -0,0 +1,326 @@
+use crate::handshake::{ClientHello, Extension as RawExtension, ExtensionPayload};
+use crate::iana::{self, Named};
+use serde_derive::Serialize;
+
+#[derive(Clone, Serialize)]
+pub struct ClientHelloReport {
+    pub record_version: Named<u16>,
+    pub legacy_version: Named<u16>,
+    pub random: String,
+    pub session_id_length: usize,
+    pub cipher_suites: Vec<Named<u16>>,
+    pub compression_methods: Vec<Named<u8>>,
+    pub extensions: Vec<Extension>,
+}
+
+impl ClientHelloReport {
+    pub fn new(hello: &ClientHello) -> Self {
+        Self {
+            record_version: iana::version(hello.record_version),
+            legacy_version: iana::version(hello.legacy_version),
+            random: hex::encode(hello.random),
+            session_id_length: hello.session_id.len(),
+            cipher_suites: hello.cipher_suites().map(iana::cipher_suite).collect(),
+            compression_methods: hello
+                .compression_methods
+                .iter()
+                .copied()
+                .map(iana::compression_method)
+                .collect(),
+            extensions: hello.extensions().map(Extension::new).collect(),
+        }
+    }
+}
+
+#[derive(Clone, Serialize)]
+pub struct Extension {
+    pub id: u16,
+    pub name: Option<&'static str>,
+    pub length: usize,
+    #[serde(flatten)]
+    pub body: ExtensionBody,
+}
+
+impl Extension {
+    fn new(ext: RawExtension) -> Self {
+        Self {
+            id: ext.ty,
+            name: iana::extension_type(ext.ty).name,
+            length: ext.data.len(),
+            body: ExtensionBody::new(&ext),
+        }
+    }
+}
+
+#[derive(Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ExtensionBody {
+    ServerName {
+        server_names: Vec<String>,
+    },
+    StatusRequest {
+        status_type: u8,
+        responder_id_count: usize,
+    },
+    SupportedGroups {
+        groups: Vec<Named<u16>>,
+    },
+    EcPointFormats {
+        formats: Vec<Named<u8>>,
+    },
+    SignatureAlgorithms {
+        schemes: Vec<Named<u16>>,
+    },
+    #[serde(rename = "application_layer_protocol_negotiation")]
+    Alpn {
+        protocols: Vec<String>,
+    },
+    SignedCertificateTimestamp,
+    Padding,
+    EncryptThenMac,
+    ExtendedMasterSecret,
+    CompressCertificate {
+        algorithms: Vec<u16>,
+    },
+    RecordSizeLimit {
+        limit: u16,
+    },
+    DelegatedCredential {
+        schemes: Vec<Named<u16>>,
+    },
+    SessionTicket {
+        ticket_length: usize,
+    },
+    PreSharedKey {
+        identities: Vec<PskIdentity>,
+        binder_lengths: Vec<usize>,
+    },
+    EarlyData,
+    SupportedVersions {
+        versions: Vec<Named<u16>>,
+    },
+    Cookie {
+        cookie: String,
+    },
+    PskKeyExchangeModes {
+        modes: Vec<Named<u8>>,
+    },
+    PostHandshakeAuth,
+    SignatureAlgorithmsCert {
+        schemes: Vec<Named<u16>>,
+    },
+    KeyShare {
+        entries: Vec<KeyShare>,
+    },
+    QuicTransportParameters {
+        data: String,
+    },
+    ApplicationSettings {
+        protocols: Vec<String>,
+    },
+    EncryptedClientHello {
+        data: String,
+    },
+    RenegotiationInfo {
+        data: String,
+    },
+    Unknown {
+        data: String,
+    },
+}
+
+#[derive(Clone, Serialize)]
+pub struct PskIdentity {
+    pub identity_length: usize,
+    pub obfuscated_ticket_age: u32,
+}
+
+#[derive(Clone, Serialize)]
+pub struct KeyShare {
+    pub group: Named<u16>,
+    pub length: usize,
+}
+
+impl ExtensionBody {
+    fn new(ext: &RawExtension) -> Self {
+        match ext.payload() {
+            ExtensionPayload::ServerName(names) => Self::ServerName {
+                server_names: names
+                    .into_iter()
+                    .map(|name| match name.name_type {
+                        0 => String::from_utf8_lossy(name.name).into_owned(),
+                        _ => hex::encode(name.name),
+                    })
+                    .collect(),
+            },
+            ExtensionPayload::StatusRequest {
+                status_type,
+                responder_ids,
+            } => Self::StatusRequest {
+                status_type,
+                responder_id_count: responder_ids.len(),
+            },
+            ExtensionPayload::SupportedGroups(groups) => Self::SupportedGroups {
+                groups: groups.into_iter().map(iana::named_group).collect(),
+            },
+            ExtensionPayload::EcPointFormats(formats) => Self::EcPointFormats {
+                formats: formats.iter().copied().map(iana::ec_point_format).collect(),
+            },
+            ExtensionPayload::SignatureAlgorithms(schemes) => Self::SignatureAlgorithms {
+                schemes: schemes.into_iter().map(iana::signature_scheme).collect(),
+            },
+            ExtensionPayload::Alpn(protocols) => Self::Alpn {
+                protocols: protocol_names(protocols),
+            },
+            ExtensionPayload::SignedCertificateTimestamp => Self::SignedCertificateTimestamp,
+            ExtensionPayload::Padding => Self::Padding,
+            ExtensionPayload::EncryptThenMac => Self::EncryptThenMac,
+            ExtensionPayload::ExtendedMasterSecret => Self::ExtendedMasterSecret,
+            ExtensionPayload::CompressCertificate(algorithms) => {
+                Self::CompressCertificate { algorithms }
+            }
+            ExtensionPayload::RecordSizeLimit(limit) => Self::RecordSizeLimit { limit },
+            ExtensionPayload::DelegatedCredential(schemes) => Self::DelegatedCredential {
+                schemes: schemes.into_iter().map(iana::signature_scheme).collect(),
+            },
+            ExtensionPayload::SessionTicket(ticket) => Self::SessionTicket {
+                ticket_length: ticket.len(),
+            },
+            ExtensionPayload::PreSharedKey {
+                identities,
+                binders,
+            } => Self::PreSharedKey {
+                identities: identities
+                    .into_iter()
+                    .map(|identity| PskIdentity {
+                        identity_length: identity.identity.len(),
+                        obfuscated_ticket_age: identity.obfuscated_ticket_age,
+                    })
+                    .collect(),
+                binder_lengths: binders.into_iter().map(|binder| binder.len()).collect(),
+            },
+            ExtensionPayload::EarlyData => Self::EarlyData,
+            ExtensionPayload::SupportedVersions(versions) => Self::SupportedVersions {
+                versions: versions.into_iter().map(iana::version).collect(),
+            },
+            ExtensionPayload::Cookie(cookie) => Self::Cookie {
+                cookie: hex::encode(cookie),
+            },
+            ExtensionPayload::PskKeyExchangeModes(modes) => Self::PskKeyExchangeModes {
+                modes: modes
+                    .iter()
+                    .copied()
+                    .map(iana::psk_key_exchange_mode)
+                    .collect(),
+            },
+            ExtensionPayload::PostHandshakeAuth => Self::PostHandshakeAuth,
+            ExtensionPayload::SignatureAlgorithmsCert(schemes) => Self::SignatureAlgorithmsCert {
+                schemes: schemes.into_iter().map(iana::signature_scheme).collect(),
+            },
+            ExtensionPayload::KeyShare(entries) => Self::KeyShare {
+                entries: entries
+                    .into_iter()
+                    .map(|entry| KeyShare {
+                        group: iana::named_group(entry.group),
+                        length: entry.key_exchange.len(),
+                    })
+                    .collect(),
+            },
+            ExtensionPayload::QuicTransportParameters(data) => Self::QuicTransportParameters {
+                data: hex::encode(data),
+            },
+            ExtensionPayload::ApplicationSettings(protocols) => Self::ApplicationSettings {
+                protocols: protocol_names(protocols),
+            },
+            ExtensionPayload::EncryptedClientHello(data) => Self::EncryptedClientHello {
+                data: hex::encode(data),
+            },
+            ExtensionPayload::RenegotiationInfo(data) => Self::RenegotiationInfo {
+                data: hex::encode(data),
+            },
+            ExtensionPayload::Unknown(data) => Self::Unknown {
+                data: hex::encode(data),
+            },
+        }
+    }
+}
+
+fn protocol_names(protocols: Vec<&[u8]>) -> Vec<String> {
+    protocols
+        .into_iter()
+        .map(|protocol| String::from_utf8_lossy(protocol).into_owned())
+        .collect()
+}
+
+#[derive(Clone, Serialize)]
+pub struct ClientHelloDiff {
+    pub random_changed: bool,
+    pub session_id_changed: bool,
+    pub cipher_suites_changed: bool,
+    pub extension_order_changed: bool,
+    pub extensions_added: Vec<Named<u16>>,
+    pub extensions_removed: Vec<Named<u16>>,
+    pub extensions_modified: Vec<Named<u16>>,
+    pub key_share_groups: (Vec<Named<u16>>, Vec<Named<u16>>),
+}
+
+impl ClientHelloDiff {
+    pub fn new(first: &ClientHello, second: &ClientHello) -> Self {
+        let first_exts = first.extensions().collect::<Vec<_>>();
+        let second_exts = second.extensions().collect::<Vec<_>>();
+
+        let extensions_added = second_exts
+            .iter()
+            .filter(|ext| find_extension(&first_exts, ext.ty).is_none())
+            .map(|ext| iana::extension_type(ext.ty))
+            .collect();
+        let extensions_removed = first_exts
+            .iter()
+            .filter(|ext| find_extension(&second_exts, ext.ty).is_none())
+            .map(|ext| iana::extension_type(ext.ty))
+            .collect();
+        let extensions_modified = first_exts
+            .iter()
+            .filter(|ext| {
+                find_extension(&second_exts, ext.ty).is_some_and(|other| other.data != ext.data)
+            })
+            .map(|ext| iana::extension_type(ext.ty))
+            .collect();
+
+        let common_order = |exts: &[RawExtension], others: &[RawExtension]| {
+            exts.iter()
+                .map(|ext| ext.ty)
+                .filter(|ty| find_extension(others, *ty).is_some())
+                .collect::<Vec<_>>()
+        };
+        let extension_order_changed =
+            common_order(&first_exts, &second_exts) != common_order(&second_exts, &first_exts);
+
+        Self {
+            random_changed: first.random != second.random,
+            session_id_changed: first.session_id != second.session_id,
+            cipher_suites_changed: !first.cipher_suites().eq(second.cipher_suites()),
+            extension_order_changed,
+            extensions_added,
+            extensions_removed,
+            extensions_modified,
+            key_share_groups: (key_share_groups(first), key_share_groups(second)),
+        }
+    }
+}
+
+fn key_share_groups(hello: &ClientHello) -> Vec<Named<u16>> {
+    hello
+        .extensions()
+        .filter_map(|ext| match ext.payload() {
+            ExtensionPayload::KeyShare(entries) => Some(entries),
+            _ => None,
+        })
+        .flatten()
+        .map(|entry| iana::named_group(entry.group))
+        .collect()
+}
+
+fn find_extension<'a>(exts: &[RawExtension<'a>], ty: u16) -> Option<RawExtension<'a>> {
+    exts.iter().find(|ext| ext.ty == ty).copied()
+}
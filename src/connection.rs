@@ -0,0 +1,55 @@
+use crate::proxy::ProxyHeader;
+use crate::report::TlsReport;
+use crate::tcp::TcpConnection;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant, SystemTime};
+use tokio::net::TcpStream;
+
+static NEXT_CONNECTION_ID: AtomicU64 = AtomicU64::new(1);
+
+/// What is known about a connection before its first request: the layers
+/// below HTTP, and when and from where it was accepted.
+pub struct Connection {
+    pub id: u64,
+    pub remote_addr: SocketAddr,
+    pub local_addr: Option<SocketAddr>,
+    pub started_at: SystemTime,
+    handshake_started: Option<Instant>,
+    /// The duration of the TLS handshake. Cleartext connections have none.
+    pub handshake_duration: Option<Duration>,
+    pub tcp: TcpConnection,
+    pub proxy: Option<ProxyHeader>,
+    pub tls: Option<TlsReport>,
+}
+
+impl Connection {
+    pub fn new(stream: &TcpStream, remote_addr: SocketAddr) -> Self {
+        Self {
+            id: NEXT_CONNECTION_ID.fetch_add(1, Ordering::Relaxed),
+            // IPv4 clients of a dual-stack listener show up as IPv4-mapped
+            // IPv6 addresses.
+            remote_addr: SocketAddr::new(remote_addr.ip().to_canonical(), remote_addr.port()),
+            local_addr: stream
+                .local_addr()
+                .ok()
+                .map(|addr| SocketAddr::new(addr.ip().to_canonical(), addr.port())),
+            started_at: SystemTime::now(),
+            handshake_started: None,
+            handshake_duration: None,
+            tcp: TcpConnection::new(stream),
+            proxy: None,
+            tls: None,
+        }
+    }
+
+    /// Starts timing the TLS handshake, once any PROXY protocol header has
+    /// been read.
+    pub fn handshake_started(&mut self) {
+        self.handshake_started = Some(Instant::now());
+    }
+
+    pub fn handshake_done(&mut self) {
+        self.handshake_duration = self.handshake_started.map(|start| start.elapsed());
+    }
+}
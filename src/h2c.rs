@@ -0,0 +1,214 @@
+//! Switching a cleartext HTTP/1.1 connection to HTTP/2 with `Upgrade: h2c`
+//! (RFC 7540, section 3.2). The request that asked for the upgrade is answered
+//! on stream 1 of the new connection, which an HTTP/2 server cannot be handed
+//! directly, so it is passed along as a HEADERS frame ahead of what the client
+//! sends.
+
+use crate::hpack::encode_literal;
+use crate::http2::{
+    FLAG_ACK, FLAG_END_HEADERS, FLAG_END_STREAM, FRAME_HEADER_LEN, FRAME_TYPE_CONTINUATION,
+    FRAME_TYPE_HEADERS, FRAME_TYPE_SETTINGS, HTTP2_PREFACE,
+};
+use crate::proxy::Rewind;
+use http::{header, Request, Response, StatusCode};
+use hyper::Body;
+use tokio::io::{self, AsyncRead, AsyncReadExt};
+
+/// The initial SETTINGS_MAX_FRAME_SIZE, which is as large as frames get
+/// before either side has seen the other's SETTINGS.
+const MAX_FRAME_LEN: usize = 1 << 14;
+
+/// Headers that only apply to the HTTP/1.1 connection (RFC 9113, section
+/// 8.2.2), along with Host, which becomes `:authority`.
+const CONNECTION_HEADERS: &[&str] = &[
+    "connection",
+    "host",
+    "http2-settings",
+    "keep-alive",
+    "proxy-connection",
+    "te",
+    "transfer-encoding",
+    "upgrade",
+];
+
+pub fn switching_protocols() -> Response<Body> {
+    Response::builder()
+        .status(StatusCode::SWITCHING_PROTOCOLS)
+        .header(header::CONNECTION, "Upgrade")
+        .header(header::UPGRADE, "h2c")
+        .body(Body::empty())
+        .unwrap()
+}
+
+/// Encodes the request that asked for the upgrade as the HEADERS frame, and
+/// any CONTINUATION frames, opening stream 1. Its fields are all literals
+/// that are not indexed, so that the client's HPACK state is left alone.
+pub fn upgrade_request<B>(req: &Request<B>) -> Vec<u8> {
+    let authority = req
+        .headers()
+        .get(header::HOST)
+        .map(|host| host.as_bytes())
+        .or_else(|| {
+            req.uri()
+                .authority()
+                .map(|authority| authority.as_str().as_bytes())
+        })
+        .unwrap_or_default();
+    let path = req.uri().path_and_query().map_or("/", |path| path.as_str());
+
+    let mut block = Vec::new();
+    encode_literal(&mut block, b":method", req.method().as_str().as_bytes());
+    encode_literal(&mut block, b":scheme", b"http");
+    encode_literal(&mut block, b":authority", authority);
+    encode_literal(&mut block, b":path", path.as_bytes());
+    for (name, value) in req.headers() {
+        if !CONNECTION_HEADERS.contains(&name.as_str()) {
+            encode_literal(&mut block, name.as_str().as_bytes(), value.as_bytes());
+        }
+    }
+
+    let mut frames = Vec::new();
+    let fragments = block.chunks(MAX_FRAME_LEN).collect::<Vec<_>>();
+    for (i, fragment) in fragments.iter().enumerate() {
+        let (ty, mut flags) = match i {
+            0 => (FRAME_TYPE_HEADERS, FLAG_END_STREAM),
+            _ => (FRAME_TYPE_CONTINUATION, 0),
+        };
+        if i == fragments.len() - 1 {
+            flags |= FLAG_END_HEADERS;
+        }
+        frames.extend(&(fragment.len() as u32).to_be_bytes()[1..]);
+        frames.extend([ty, flags]);
+        frames.extend(1u32.to_be_bytes());
+        frames.extend(*fragment);
+    }
+    frames
+}
+
+/// Reads the connection preface a client sends after switching to h2c, and
+/// slips the frames of the upgrade request in right after its SETTINGS frame,
+/// where an HTTP/2 server sees them as the first stream the client opened.
+pub async fn accept_h2c<S>(mut stream: S, request: Vec<u8>) -> io::Result<Rewind<S>>
+where
+    S: AsyncRead + Unpin,
+{
+    let mut prefix = vec![0; HTTP2_PREFACE.len() + FRAME_HEADER_LEN];
+    stream.read_exact(&mut prefix).await?;
+    let (preface, header) = prefix.split_at(HTTP2_PREFACE.len());
+    let len = u32::from_be_bytes([0, header[0], header[1], header[2]]) as usize;
+    if preface != HTTP2_PREFACE
+        || header[3] != FRAME_TYPE_SETTINGS
+        || header[4] & FLAG_ACK != 0
+        || len > MAX_FRAME_LEN
+    {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "h2c connection does not start with a preface and SETTINGS",
+        ));
+    }
+    let mut settings = vec![0; len];
+    stream.read_exact(&mut settings).await?;
+    prefix.extend(settings);
+    prefix.extend(request);
+    Ok(Rewind::new(stream, prefix))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hpack::{BlockEncoding, Representation, Source};
+    use httlib_hpack::Decoder;
+
+    fn frame(ty: u8, flags: u8, stream_id: u32, payload: &[u8]) -> Vec<u8> {
+        let mut frame = (payload.len() as u32).to_be_bytes()[1..].to_vec();
+        frame.extend([ty, flags]);
+        frame.extend(stream_id.to_be_bytes());
+        frame.extend(payload);
+        frame
+    }
+
+    fn curl_upgrade() -> Request<()> {
+        Request::get("/index.json")
+            .header("host", "127.0.0.1:8080")
+            .header("user-agent", "curl/7.88.1")
+            .header("accept", "*/*")
+            .header("connection", "Upgrade, HTTP2-Settings")
+            .header("upgrade", "h2c")
+            .header("http2-settings", "AAMAAABkAAQCAAAAAAIAAAAA")
+            .body(())
+            .unwrap()
+    }
+
+    #[test]
+    fn encode_upgrade_request() {
+        let frames = upgrade_request(&curl_upgrade());
+        let (header, block) = frames.split_at(FRAME_HEADER_LEN);
+        assert_eq!(header[..3], (block.len() as u32).to_be_bytes()[1..]);
+        assert_eq!(header[3..], [0x1, 0x5, 0, 0, 0, 1]);
+
+        let mut decoder = Decoder::default();
+        let mut headers = Vec::new();
+        decoder.decode(&mut block.to_vec(), &mut headers).unwrap();
+        let headers = headers
+            .iter()
+            .map(|(name, value, _)| {
+                let text = |bytes| String::from_utf8_lossy(bytes).into_owned();
+                format!("{}: {}", text(name), text(value))
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            headers,
+            [
+                ":method: GET",
+                ":scheme: http",
+                ":authority: 127.0.0.1:8080",
+                ":path: /index.json",
+                "user-agent: curl/7.88.1",
+                "accept: */*",
+            ]
+        );
+        let encoding = BlockEncoding::scan(block).unwrap();
+        assert!(encoding.fields.iter().all(|field| {
+            field.representation == Representation::WithoutIndexing && field.name == Source::Raw
+        }));
+    }
+
+    #[test]
+    fn split_large_upgrade_request() {
+        let mut req = curl_upgrade();
+        let value = "a".repeat(MAX_FRAME_LEN);
+        req.headers_mut().insert("x-large", value.parse().unwrap());
+        let frames = upgrade_request(&req);
+        let second = FRAME_HEADER_LEN + MAX_FRAME_LEN;
+        assert_eq!(frames[3..5], [FRAME_TYPE_HEADERS, FLAG_END_STREAM]);
+        assert_eq!(
+            frames[second + 3..second + 5],
+            [FRAME_TYPE_CONTINUATION, FLAG_END_HEADERS]
+        );
+    }
+
+    #[tokio::test]
+    async fn insert_upgrade_request_after_settings() {
+        let settings = frame(0x4, 0, 0, &[0, 3, 0, 0, 0, 100]);
+        let ping = frame(0x6, 0, 0, &[0; 8]);
+        let client = [HTTP2_PREFACE, &settings, &ping].concat();
+        let request = upgrade_request(&curl_upgrade());
+
+        let mut stream = accept_h2c(&client[..], request.clone()).await.unwrap();
+        let mut received = Vec::new();
+        stream.read_to_end(&mut received).await.unwrap();
+        assert_eq!(
+            received,
+            [HTTP2_PREFACE, &settings, &request, &ping].concat()
+        );
+    }
+
+    #[tokio::test]
+    async fn reject_missing_preface() {
+        let data = b"GET / HTTP/1.1\r\nHost: localhost\r\n\r\n";
+        assert!(accept_h2c(&data[..], Vec::new()).await.is_err());
+
+        let client = [HTTP2_PREFACE, &frame(0x4, 0x1, 0, &[])].concat();
+        assert!(accept_h2c(&client[..], Vec::new()).await.is_err());
+    }
+}
@@ -1,4 +1,6 @@
+use crate::priority_tree::PriorityTreeReport;
 use crate::report::Report;
+use eth_blockies::{BlockiesGenerator, EthBlockies};
 use http::{Request, Response, StatusCode};
 use hyper::Body;
 use include_dir::{include_dir, Dir};
@@ -14,8 +16,21 @@ struct IndexTemplate {
     report: Report,
 }
 
+#[derive(TemplateOnce)]
+#[template(path = "priority_probe.stpl")]
+struct PriorityProbeTemplate {}
+
+#[derive(TemplateOnce)]
+#[template(path = "priority_tree.stpl")]
+struct PriorityTreeTemplate {
+    tree: Option<PriorityTreeReport>,
+}
+
 const IDENTICON_SIZE: usize = 32;
 
+/// Number of resources of each type requested by the priority probe page.
+const PROBE_COUNT: usize = 4;
+
 pub async fn handle_request(
     req: Request<Body>,
     report: Report,
@@ -42,16 +57,43 @@ pub async fn handle_request(
                 .flatten()
                 .unwrap_or_default();
             let seed = hex::decode(hex).unwrap_or_default();
-            let data =
-                eth_blockies::eth_blockies_png_data(seed, (IDENTICON_SIZE, IDENTICON_SIZE), true);
+            let data = EthBlockies::compressed_png_data(seed, (IDENTICON_SIZE, IDENTICON_SIZE));
             return Ok(Response::builder()
                 .header("Content-Type", "image/png")
                 .body(Body::from(data))
                 .unwrap());
         }
+        "/priority" => {
+            let ctx = PriorityProbeTemplate {};
+            return Ok(Response::builder()
+                .header("Content-Type", "text/html")
+                .header("Cache-Control", "no-store")
+                .body(Body::from(ctx.render_once().unwrap()))
+                .unwrap());
+        }
+        "/priority/tree" => {
+            let tree = report.http2.map(|http2| http2.priority_tree);
+            let ctx = PriorityTreeTemplate { tree };
+            return Ok(Response::builder()
+                .header("Content-Type", "text/html")
+                .header("Cache-Control", "no-store")
+                .body(Body::from(ctx.render_once().unwrap()))
+                .unwrap());
+        }
+        "/priority/tree.json" => {
+            let tree = report.http2.map(|http2| http2.priority_tree);
+            return Ok(Response::builder()
+                .header("Content-Type", "application/json")
+                .body(Body::from(serde_json::to_string_pretty(&tree).unwrap()))
+                .unwrap());
+        }
         _ => {}
     }
 
+    if let Some(ext) = path.strip_prefix("/priority/resource.") {
+        return Ok(probe_resource(path, ext));
+    }
+
     if let Some(file) = STATIC_DIR.get_file(path.trim_start_matches('/')) {
         return Ok(Response::builder()
             .header("Content-Type", path_to_mime(path))
@@ -65,6 +107,35 @@ pub async fn handle_request(
         .unwrap())
 }
 
+/// Serves the sub-resources loaded by the priority probe page. They are never
+/// cached, so every load opens a new stream.
+fn probe_resource(path: &str, ext: &str) -> Response<Body> {
+    let body = match ext {
+        "css" => Body::from(
+            "@font-face { font-family: probe; src: url(/priority/resource.woff2?n=1); }\n\
+             p { font-family: probe, monospace; }\n",
+        ),
+        "js" => Body::from("\n"),
+        "json" => Body::from("{}\n"),
+        "png" => Body::from(EthBlockies::compressed_png_data(
+            Vec::<u8>::new(),
+            (IDENTICON_SIZE, IDENTICON_SIZE),
+        )),
+        "woff2" => Body::empty(),
+        _ => {
+            return Response::builder()
+                .status(StatusCode::NOT_FOUND)
+                .body(Body::from("404"))
+                .unwrap()
+        }
+    };
+    Response::builder()
+        .header("Content-Type", path_to_mime(path))
+        .header("Cache-Control", "no-store")
+        .body(body)
+        .unwrap()
+}
+
 fn path_to_mime(path: &str) -> &'static str {
     let ext = Path::new(path)
         .extension()
@@ -72,6 +143,6 @@ fn path_to_mime(path: &str) -> &'static str {
         .to_str()
         .unwrap_or_default();
     mimext::ext_to_mime(ext)
-        .get(0)
+        .first()
         .unwrap_or(&"application/octet-stream")
 }
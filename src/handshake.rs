@@ -0,0 +1,641 @@
+pub const CONTENT_TYPE_CHANGE_CIPHER_SPEC: u8 = 20;
+pub const CONTENT_TYPE_HANDSHAKE: u8 = 22;
+pub const HANDSHAKE_CLIENT_HELLO: u8 = 1;
+pub const HANDSHAKE_SERVER_HELLO: u8 = 2;
+pub const HANDSHAKE_SERVER_KEY_EXCHANGE: u8 = 12;
+pub const HANDSHAKE_SERVER_HELLO_DONE: u8 = 14;
+
+pub const RECORD_HEADER_LEN: usize = 5;
+
+pub const EXT_SERVER_NAME: u16 = 0x0000;
+pub const EXT_STATUS_REQUEST: u16 = 0x0005;
+pub const EXT_SUPPORTED_GROUPS: u16 = 0x000a;
+pub const EXT_EC_POINT_FORMATS: u16 = 0x000b;
+pub const EXT_SIGNATURE_ALGORITHMS: u16 = 0x000d;
+pub const EXT_ALPN: u16 = 0x0010;
+pub const EXT_SIGNED_CERTIFICATE_TIMESTAMP: u16 = 0x0012;
+pub const EXT_PADDING: u16 = 0x0015;
+pub const EXT_ENCRYPT_THEN_MAC: u16 = 0x0016;
+pub const EXT_EXTENDED_MASTER_SECRET: u16 = 0x0017;
+pub const EXT_COMPRESS_CERTIFICATE: u16 = 0x001b;
+pub const EXT_RECORD_SIZE_LIMIT: u16 = 0x001c;
+pub const EXT_DELEGATED_CREDENTIAL: u16 = 0x0022;
+pub const EXT_SESSION_TICKET: u16 = 0x0023;
+pub const EXT_PRE_SHARED_KEY: u16 = 0x0029;
+pub const EXT_EARLY_DATA: u16 = 0x002a;
+pub const EXT_SUPPORTED_VERSIONS: u16 = 0x002b;
+pub const EXT_COOKIE: u16 = 0x002c;
+pub const EXT_PSK_KEY_EXCHANGE_MODES: u16 = 0x002d;
+pub const EXT_POST_HANDSHAKE_AUTH: u16 = 0x0031;
+pub const EXT_SIGNATURE_ALGORITHMS_CERT: u16 = 0x0032;
+pub const EXT_KEY_SHARE: u16 = 0x0033;
+pub const EXT_QUIC_TRANSPORT_PARAMETERS: u16 = 0x0039;
+pub const EXT_APPLICATION_SETTINGS_OLD: u16 = 0x4469;
+pub const EXT_APPLICATION_SETTINGS: u16 = 0x44cd;
+pub const EXT_ENCRYPTED_CLIENT_HELLO: u16 = 0xfe0d;
+pub const EXT_RENEGOTIATION_INFO: u16 = 0xff01;
+
+pub struct Record<'a> {
+    pub content_type: u8,
+    pub version: u16,
+    pub fragment: &'a [u8],
+}
+
+impl<'a> Record<'a> {
+    /// Reads a single TLS record from the head of `data`, returning the record
+    /// and the number of bytes it occupies.
+    pub fn parse(data: &'a [u8]) -> Option<(Self, usize)> {
+        let mut reader = Reader::new(data);
+        let content_type = reader.u8()?;
+        let version = reader.u16()?;
+        let fragment = reader.u16_prefixed()?;
+        let record = Self {
+            content_type,
+            version,
+            fragment,
+        };
+        Some((record, RECORD_HEADER_LEN + fragment.len()))
+    }
+}
+
+/// A handshake message reassembled from the fragments of one or more records.
+pub struct HandshakeMessage {
+    pub record_version: u16,
+    pub msg_type: u8,
+    pub body: Vec<u8>,
+    pub records: usize,
+    /// Total length of the records carrying the message.
+    pub len: usize,
+}
+
+impl HandshakeMessage {
+    /// Reassembles the first handshake message from the records at the head of
+    /// `data`. Returns `None` if the message is not complete yet or `data` does
+    /// not start with a handshake record.
+    pub fn reassemble(data: &[u8]) -> Option<Self> {
+        let mut offset = 0;
+        let mut records = 0;
+        let mut record_version = None;
+        let mut message = Vec::new();
+        while let Some((record, len)) = Record::parse(&data[offset..]) {
+            if record.content_type != CONTENT_TYPE_HANDSHAKE {
+                return None;
+            }
+            record_version.get_or_insert(record.version);
+            message.extend_from_slice(record.fragment);
+            offset += len;
+            records += 1;
+
+            let mut reader = Reader::new(&message);
+            if let (Some(msg_type), Some(body)) = (reader.u8(), reader.u24_prefixed()) {
+                return Some(Self {
+                    record_version: record_version?,
+                    msg_type,
+                    body: body.to_vec(),
+                    records,
+                    len: offset,
+                });
+            }
+        }
+        None
+    }
+}
+
+#[derive(Clone)]
+pub struct ClientHello<'a> {
+    pub record_version: u16,
+    pub legacy_version: u16,
+    pub random: &'a [u8],
+    pub session_id: &'a [u8],
+    pub compression_methods: &'a [u8],
+    cipher_suites: &'a [u8],
+    extensions: &'a [u8],
+}
+
+impl<'a> ClientHello<'a> {
+    /// Parses the body of a ClientHello handshake message.
+    pub fn parse(record_version: u16, body: &'a [u8]) -> Option<Self> {
+        let mut reader = Reader::new(body);
+        let legacy_version = reader.u16()?;
+        let random = reader.take(32)?;
+        let session_id = reader.u8_prefixed()?;
+        let cipher_suites = reader.u16_prefixed()?;
+        let compression_methods = reader.u8_prefixed()?;
+        let extensions = if reader.is_empty() {
+            &[]
+        } else {
+            reader.u16_prefixed()?
+        };
+        if cipher_suites.len() % 2 != 0 {
+            return None;
+        }
+        if Extensions(Reader::new(extensions)).any(|ext| ext.is_none()) {
+            return None;
+        }
+        Some(Self {
+            record_version,
+            legacy_version,
+            random,
+            session_id,
+            compression_methods,
+            cipher_suites,
+            extensions,
+        })
+    }
+
+    pub fn cipher_suites(&self) -> impl Iterator<Item = u16> + 'a {
+        u16_items(self.cipher_suites)
+    }
+
+    pub fn extensions(&self) -> impl Iterator<Item = Extension<'a>> + 'a {
+        Extensions(Reader::new(self.extensions)).flatten()
+    }
+}
+
+/// SHA-256 of "HelloRetryRequest", sent as the random of a HelloRetryRequest.
+const HELLO_RETRY_REQUEST_RANDOM: [u8; 32] = [
+    0xcf, 0x21, 0xad, 0x74, 0xe5, 0x9a, 0x61, 0x11, 0xbe, 0x1d, 0x8c, 0x02, 0x1e, 0x65, 0xb8, 0x91,
+    0xc2, 0xa2, 0x11, 0x16, 0x7a, 0xbb, 0x8c, 0x5e, 0x07, 0x9e, 0x09, 0xe2, 0xc8, 0xa8, 0x33, 0x9c,
+];
+
+pub struct ServerHello<'a> {
+    pub random: &'a [u8],
+    pub session_id: &'a [u8],
+    extensions: &'a [u8],
+}
+
+impl<'a> ServerHello<'a> {
+    /// Parses the body of a ServerHello handshake message.
+    pub fn parse(body: &'a [u8]) -> Option<Self> {
+        let mut reader = Reader::new(body);
+        let _legacy_version = reader.u16()?;
+        let random = reader.take(32)?;
+        let session_id = reader.u8_prefixed()?;
+        let _cipher_suite = reader.u16()?;
+        let _compression_method = reader.u8()?;
+        let extensions = if reader.is_empty() {
+            &[]
+        } else {
+            reader.u16_prefixed()?
+        };
+        Some(Self {
+            random,
+            session_id,
+            extensions,
+        })
+    }
+
+    pub fn is_hello_retry_request(&self) -> bool {
+        self.random == HELLO_RETRY_REQUEST_RANDOM
+    }
+
+    /// The group of the server's key share (TLS 1.3 only).
+    pub fn key_share_group(&self) -> Option<u16> {
+        self.extension(EXT_KEY_SHARE)
+            .and_then(|data| Reader::new(data).u16())
+    }
+
+    /// Whether the server accepted one of the offered pre-shared keys (TLS 1.3 only).
+    pub fn has_pre_shared_key(&self) -> bool {
+        self.extension(EXT_PRE_SHARED_KEY).is_some()
+    }
+
+    fn extension(&self, ty: u16) -> Option<&'a [u8]> {
+        Extensions(Reader::new(self.extensions))
+            .flatten()
+            .find(|ext| ext.ty == ty)
+            .map(|ext| ext.data)
+    }
+}
+
+/// The parameters of a TLS 1.2 ECDHE ServerKeyExchange.
+pub struct ServerKeyExchange {
+    pub named_group: u16,
+    pub signature_scheme: u16,
+}
+
+impl ServerKeyExchange {
+    const CURVE_TYPE_NAMED_CURVE: u8 = 3;
+
+    /// Parses the body of a ServerKeyExchange handshake message. Only named
+    /// curves are supported.
+    pub fn parse(body: &[u8]) -> Option<Self> {
+        let mut reader = Reader::new(body);
+        if reader.u8()? != Self::CURVE_TYPE_NAMED_CURVE {
+            return None;
+        }
+        let named_group = reader.u16()?;
+        let _public_key = reader.u8_prefixed()?;
+        let signature_scheme = reader.u16()?;
+        Some(Self {
+            named_group,
+            signature_scheme,
+        })
+    }
+}
+
+struct Extensions<'a>(Reader<'a>);
+
+impl<'a> Iterator for Extensions<'a> {
+    type Item = Option<Extension<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.0.is_empty() {
+            return None;
+        }
+        let ext = self.0.u16().and_then(|ty| {
+            let data = self.0.u16_prefixed()?;
+            Some(Extension { ty, data })
+        });
+        if ext.is_none() {
+            self.0 = Reader::new(&[]);
+        }
+        Some(ext)
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct Extension<'a> {
+    pub ty: u16,
+    pub data: &'a [u8],
+}
+
+impl<'a> Extension<'a> {
+    /// Decodes the extension body. Unrecognised or malformed extensions are
+    /// returned as `ExtensionPayload::Unknown` with their raw bytes.
+    pub fn payload(&self) -> ExtensionPayload<'a> {
+        self.parse_payload()
+            .unwrap_or(ExtensionPayload::Unknown(self.data))
+    }
+
+    fn parse_payload(&self) -> Option<ExtensionPayload<'a>> {
+        let mut reader = Reader::new(self.data);
+        let payload = match self.ty {
+            EXT_SERVER_NAME if self.data.is_empty() => ExtensionPayload::ServerName(Vec::new()),
+            EXT_SERVER_NAME => {
+                let mut list = Reader::new(reader.u16_prefixed()?);
+                let mut names = Vec::new();
+                while !list.is_empty() {
+                    let name_type = list.u8()?;
+                    let name = list.u16_prefixed()?;
+                    names.push(ServerName { name_type, name });
+                }
+                ExtensionPayload::ServerName(names)
+            }
+            EXT_STATUS_REQUEST => {
+                let status_type = reader.u8()?;
+                let responder_ids = if status_type == 1 {
+                    let ids = u16_prefixed_list(reader.u16_prefixed()?)?;
+                    reader.u16_prefixed()?;
+                    ids
+                } else {
+                    reader.rest();
+                    Vec::new()
+                };
+                ExtensionPayload::StatusRequest {
+                    status_type,
+                    responder_ids,
+                }
+            }
+            EXT_SUPPORTED_GROUPS => {
+                ExtensionPayload::SupportedGroups(u16_list(reader.u16_prefixed()?)?)
+            }
+            EXT_EC_POINT_FORMATS => ExtensionPayload::EcPointFormats(reader.u8_prefixed()?),
+            EXT_SIGNATURE_ALGORITHMS => {
+                ExtensionPayload::SignatureAlgorithms(u16_list(reader.u16_prefixed()?)?)
+            }
+            EXT_ALPN => ExtensionPayload::Alpn(u8_prefixed_list(reader.u16_prefixed()?)?),
+            EXT_SIGNED_CERTIFICATE_TIMESTAMP => ExtensionPayload::SignedCertificateTimestamp,
+            EXT_PADDING => {
+                reader.rest();
+                ExtensionPayload::Padding
+            }
+            EXT_ENCRYPT_THEN_MAC => ExtensionPayload::EncryptThenMac,
+            EXT_EXTENDED_MASTER_SECRET => ExtensionPayload::ExtendedMasterSecret,
+            EXT_COMPRESS_CERTIFICATE => {
+                ExtensionPayload::CompressCertificate(u16_list(reader.u8_prefixed()?)?)
+            }
+            EXT_RECORD_SIZE_LIMIT => ExtensionPayload::RecordSizeLimit(reader.u16()?),
+            EXT_DELEGATED_CREDENTIAL => {
+                ExtensionPayload::DelegatedCredential(u16_list(reader.u16_prefixed()?)?)
+            }
+            EXT_SESSION_TICKET => ExtensionPayload::SessionTicket(reader.rest()),
+            EXT_PRE_SHARED_KEY => {
+                let mut list = Reader::new(reader.u16_prefixed()?);
+                let mut identities = Vec::new();
+                while !list.is_empty() {
+                    let identity = list.u16_prefixed()?;
+                    let obfuscated_ticket_age = list.u32()?;
+                    identities.push(PskIdentity {
+                        identity,
+                        obfuscated_ticket_age,
+                    });
+                }
+                let binders = u8_prefixed_list(reader.u16_prefixed()?)?;
+                ExtensionPayload::PreSharedKey {
+                    identities,
+                    binders,
+                }
+            }
+            EXT_EARLY_DATA => ExtensionPayload::EarlyData,
+            EXT_SUPPORTED_VERSIONS => {
+                ExtensionPayload::SupportedVersions(u16_list(reader.u8_prefixed()?)?)
+            }
+            EXT_COOKIE => ExtensionPayload::Cookie(reader.u16_prefixed()?),
+            EXT_PSK_KEY_EXCHANGE_MODES => {
+                ExtensionPayload::PskKeyExchangeModes(reader.u8_prefixed()?)
+            }
+            EXT_POST_HANDSHAKE_AUTH => ExtensionPayload::PostHandshakeAuth,
+            EXT_SIGNATURE_ALGORITHMS_CERT => {
+                ExtensionPayload::SignatureAlgorithmsCert(u16_list(reader.u16_prefixed()?)?)
+            }
+            EXT_KEY_SHARE => {
+                let mut list = Reader::new(reader.u16_prefixed()?);
+                let mut entries = Vec::new();
+                while !list.is_empty() {
+                    let group = list.u16()?;
+                    let key_exchange = list.u16_prefixed()?;
+                    entries.push(KeyShareEntry {
+                        group,
+                        key_exchange,
+                    });
+                }
+                ExtensionPayload::KeyShare(entries)
+            }
+            EXT_QUIC_TRANSPORT_PARAMETERS => {
+                ExtensionPayload::QuicTransportParameters(reader.rest())
+            }
+            EXT_APPLICATION_SETTINGS_OLD | EXT_APPLICATION_SETTINGS => {
+                ExtensionPayload::ApplicationSettings(u8_prefixed_list(reader.u16_prefixed()?)?)
+            }
+            EXT_ENCRYPTED_CLIENT_HELLO => ExtensionPayload::EncryptedClientHello(reader.rest()),
+            EXT_RENEGOTIATION_INFO => ExtensionPayload::RenegotiationInfo(reader.u8_prefixed()?),
+            _ => ExtensionPayload::Unknown(reader.rest()),
+        };
+        if reader.is_empty() {
+            Some(payload)
+        } else {
+            None
+        }
+    }
+}
+
+pub enum ExtensionPayload<'a> {
+    ServerName(Vec<ServerName<'a>>),
+    StatusRequest {
+        status_type: u8,
+        responder_ids: Vec<&'a [u8]>,
+    },
+    SupportedGroups(Vec<u16>),
+    EcPointFormats(&'a [u8]),
+    SignatureAlgorithms(Vec<u16>),
+    Alpn(Vec<&'a [u8]>),
+    SignedCertificateTimestamp,
+    Padding,
+    EncryptThenMac,
+    ExtendedMasterSecret,
+    CompressCertificate(Vec<u16>),
+    RecordSizeLimit(u16),
+    DelegatedCredential(Vec<u16>),
+    SessionTicket(&'a [u8]),
+    PreSharedKey {
+        identities: Vec<PskIdentity<'a>>,
+        binders: Vec<&'a [u8]>,
+    },
+    EarlyData,
+    SupportedVersions(Vec<u16>),
+    Cookie(&'a [u8]),
+    PskKeyExchangeModes(&'a [u8]),
+    PostHandshakeAuth,
+    SignatureAlgorithmsCert(Vec<u16>),
+    KeyShare(Vec<KeyShareEntry<'a>>),
+    QuicTransportParameters(&'a [u8]),
+    ApplicationSettings(Vec<&'a [u8]>),
+    EncryptedClientHello(&'a [u8]),
+    RenegotiationInfo(&'a [u8]),
+    Unknown(&'a [u8]),
+}
+
+pub struct ServerName<'a> {
+    pub name_type: u8,
+    pub name: &'a [u8],
+}
+
+pub struct PskIdentity<'a> {
+    pub identity: &'a [u8],
+    pub obfuscated_ticket_age: u32,
+}
+
+pub struct KeyShareEntry<'a> {
+    pub group: u16,
+    pub key_exchange: &'a [u8],
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        if self.data.len() < len {
+            return None;
+        }
+        let (head, tail) = self.data.split_at(len);
+        self.data = tail;
+        Some(head)
+    }
+
+    fn rest(&mut self) -> &'a [u8] {
+        std::mem::take(&mut self.data)
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        self.take(1).map(|b| b[0])
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        self.take(2).map(|b| u16::from_be_bytes([b[0], b[1]]))
+    }
+
+    fn u24(&mut self) -> Option<u32> {
+        self.take(3)
+            .map(|b| u32::from_be_bytes([0, b[0], b[1], b[2]]))
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        self.take(4)
+            .map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    fn u8_prefixed(&mut self) -> Option<&'a [u8]> {
+        let len = self.u8()? as usize;
+        self.take(len)
+    }
+
+    fn u16_prefixed(&mut self) -> Option<&'a [u8]> {
+        let len = self.u16()? as usize;
+        self.take(len)
+    }
+
+    fn u24_prefixed(&mut self) -> Option<&'a [u8]> {
+        let len = self.u24()? as usize;
+        self.take(len)
+    }
+}
+
+fn u16_items(data: &[u8]) -> impl Iterator<Item = u16> + '_ {
+    data.chunks_exact(2)
+        .map(|b| u16::from_be_bytes([b[0], b[1]]))
+}
+
+fn u16_list(data: &[u8]) -> Option<Vec<u16>> {
+    if data.len() % 2 != 0 {
+        return None;
+    }
+    Some(u16_items(data).collect())
+}
+
+fn u8_prefixed_list(data: &[u8]) -> Option<Vec<&[u8]>> {
+    let mut reader = Reader::new(data);
+    let mut items = Vec::new();
+    while !reader.is_empty() {
+        items.push(reader.u8_prefixed()?);
+    }
+    Some(items)
+}
+
+fn u16_prefixed_list(data: &[u8]) -> Option<Vec<&[u8]>> {
+    let mut reader = Reader::new(data);
+    let mut items = Vec::new();
+    while !reader.is_empty() {
+        items.push(reader.u16_prefixed()?);
+    }
+    Some(items)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A ClientHello sent by curl 7.88.1 with OpenSSL 3.0.
+    const CURL_CLIENT_HELLO: &[u8] = include_bytes!("../tests/data/curl_client_hello.bin");
+
+    fn client_hello_body() -> Vec<u8> {
+        HandshakeMessage::reassemble(CURL_CLIENT_HELLO)
+            .unwrap()
+            .body
+    }
+
+    #[test]
+    fn parse_client_hello() {
+        let msg = HandshakeMessage::reassemble(CURL_CLIENT_HELLO).unwrap();
+        assert_eq!(msg.msg_type, HANDSHAKE_CLIENT_HELLO);
+        assert_eq!(msg.record_version, 0x0301);
+        assert_eq!(msg.records, 1);
+        assert_eq!(msg.len, CURL_CLIENT_HELLO.len());
+
+        let hello = ClientHello::parse(msg.record_version, &msg.body).unwrap();
+        assert_eq!(hello.legacy_version, 0x0303);
+        assert_eq!(hello.session_id.len(), 32);
+        assert_eq!(hello.cipher_suites().count(), 31);
+        assert_eq!(hello.cipher_suites().next(), Some(0x1302));
+
+        let server_names = hello.extensions().find_map(|ext| match ext.payload() {
+            ExtensionPayload::ServerName(names) => Some(names),
+            _ => None,
+        });
+        assert_eq!(server_names.unwrap()[0].name, b"localhost");
+        let alpn = hello.extensions().find_map(|ext| match ext.payload() {
+            ExtensionPayload::Alpn(protocols) => Some(protocols),
+            _ => None,
+        });
+        assert_eq!(alpn, Some(vec![&b"h2"[..], &b"http/1.1"[..]]));
+    }
+
+    #[test]
+    fn reassemble_client_hello_split_over_records() {
+        let body = &CURL_CLIENT_HELLO[RECORD_HEADER_LEN..];
+        let mut data = Vec::new();
+        for fragment in body.chunks(100) {
+            data.extend([CONTENT_TYPE_HANDSHAKE, 0x03, 0x01]);
+            data.extend((fragment.len() as u16).to_be_bytes());
+            data.extend(fragment);
+        }
+        let msg = HandshakeMessage::reassemble(&data).unwrap();
+        assert_eq!(msg.records, 6);
+        assert_eq!(msg.len, data.len());
+        assert_eq!(msg.body, client_hello_body());
+
+        for len in 0..data.len() {
+            assert!(HandshakeMessage::reassemble(&data[..len]).is_none());
+        }
+    }
+
+    #[test]
+    fn reject_truncated_client_hello() {
+        for len in 0..CURL_CLIENT_HELLO.len() {
+            assert!(HandshakeMessage::reassemble(&CURL_CLIENT_HELLO[..len]).is_none());
+        }
+
+        // A ClientHello may end right after the compression methods, at
+        // offset 133 here, since extensions are optional.
+        let body = client_hello_body();
+        for len in (0..body.len()).filter(|len| *len != 133) {
+            assert!(ClientHello::parse(0x0301, &body[..len]).is_none());
+        }
+        let hello = ClientHello::parse(0x0301, &body[..133]).unwrap();
+        assert_eq!(hello.extensions().count(), 0);
+    }
+
+    #[test]
+    fn reject_malformed_client_hello() {
+        let body = client_hello_body();
+
+        // An odd cipher suites length.
+        let mut data = body.clone();
+        data[68] = 61;
+        assert!(ClientHello::parse(0x0301, &data).is_none());
+
+        // An extension running past the end of the extensions block.
+        let mut data = body.clone();
+        data[133..135].copy_from_slice(&10u16.to_be_bytes());
+        assert!(ClientHello::parse(0x0301, &data).is_none());
+
+        // A record that is not a handshake record.
+        let mut data = CURL_CLIENT_HELLO.to_vec();
+        data[0] = 23;
+        assert!(HandshakeMessage::reassemble(&data).is_none());
+    }
+
+    #[test]
+    fn corrupted_extensions_do_not_panic() {
+        let body = client_hello_body();
+        for offset in 133..body.len() {
+            for value in [0x00, 0x01, 0x7f, 0xff] {
+                let mut data = body.clone();
+                data[offset] = value;
+                if let Some(hello) = ClientHello::parse(0x0301, &data) {
+                    for ext in hello.extensions() {
+                        let _ = ext.payload();
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn reject_truncated_server_messages() {
+        assert!(ServerHello::parse(&[0x03, 0x03]).is_none());
+        assert!(ServerHello::parse(&[0; 37]).is_none());
+        assert!(ServerKeyExchange::parse(&[3, 0, 29, 32]).is_none());
+        assert!(ServerKeyExchange::parse(&[1, 0, 29]).is_none());
+    }
+}
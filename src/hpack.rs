@@ -0,0 +1,333 @@
+use serde_derive::Serialize;
+
+use crate::http2::Frame;
+
+const STATIC_TABLE_LEN: usize = 61;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Representation {
+    Indexed,
+    WithIndexing,
+    WithoutIndexing,
+    NeverIndexed,
+}
+
+/// Where a header name or value came from: a static or dynamic table entry,
+/// or a string literal with or without Huffman coding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Source {
+    Static,
+    Dynamic,
+    Huffman,
+    Raw,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct FieldEncoding {
+    pub representation: Representation,
+    pub name: Source,
+    pub value: Option<Source>,
+}
+
+impl FieldEncoding {
+    fn code(&self) -> String {
+        let representation = match self.representation {
+            Representation::Indexed => 'i',
+            Representation::WithIndexing => 'l',
+            Representation::WithoutIndexing => 'n',
+            Representation::NeverIndexed => 'v',
+        };
+        let source = |source: Source| match source {
+            Source::Static => 's',
+            Source::Dynamic => 'd',
+            Source::Huffman => 'h',
+            Source::Raw => 'r',
+        };
+        let mut code = format!("{representation}{}", source(self.name));
+        if let Some(value) = self.value {
+            code.push(source(value));
+        }
+        code
+    }
+}
+
+/// The representations used in a header block, without decoding it.
+#[derive(Debug, Default)]
+pub struct BlockEncoding {
+    pub table_size_updates: Vec<u32>,
+    pub fields: Vec<FieldEncoding>,
+}
+
+impl BlockEncoding {
+    /// Walks the field representations of a complete header block.
+    pub fn scan(mut block: &[u8]) -> Option<Self> {
+        let mut encoding = Self::default();
+        while let Some(&octet) = block.first() {
+            let (representation, prefix) = if octet & 0x80 != 0 {
+                (Representation::Indexed, 7)
+            } else if octet & 0x40 != 0 {
+                (Representation::WithIndexing, 6)
+            } else if octet & 0x20 != 0 {
+                let size = integer(&mut block, 5)?;
+                encoding.table_size_updates.push(size.try_into().ok()?);
+                continue;
+            } else if octet & 0x10 != 0 {
+                (Representation::NeverIndexed, 4)
+            } else {
+                (Representation::WithoutIndexing, 4)
+            };
+
+            let index = integer(&mut block, prefix)?;
+            let name = match index {
+                0 if representation == Representation::Indexed => return None,
+                0 => string(&mut block)?,
+                1..=STATIC_TABLE_LEN => Source::Static,
+                _ => Source::Dynamic,
+            };
+            let value = if representation == Representation::Indexed {
+                None
+            } else {
+                Some(string(&mut block)?)
+            };
+            encoding.fields.push(FieldEncoding {
+                representation,
+                name,
+                value,
+            });
+        }
+        Some(encoding)
+    }
+}
+
+/// Appends a literal header field without indexing and with a literal name
+/// (RFC 7541, section 6.2.2), which leaves the dynamic table as it is.
+pub fn encode_literal(block: &mut Vec<u8>, name: &[u8], value: &[u8]) {
+    block.push(0);
+    for string in [name, value] {
+        encode_integer(block, string.len(), 7);
+        block.extend_from_slice(string);
+    }
+}
+
+/// Appends an integer with an N-bit prefix, leaving the bits before the prefix
+/// unset.
+fn encode_integer(block: &mut Vec<u8>, mut value: usize, prefix: u32) {
+    let max = (1usize << prefix) - 1;
+    if value < max {
+        block.push(value as u8);
+        return;
+    }
+    block.push(max as u8);
+    value -= max;
+    while value >= 0x80 {
+        block.push((value & 0x7f) as u8 | 0x80);
+        value >>= 7;
+    }
+    block.push(value as u8);
+}
+
+/// Reads an integer with an N-bit prefix (RFC 7541, section 5.1).
+fn integer(block: &mut &[u8], prefix: u32) -> Option<usize> {
+    let (&first, mut rest) = block.split_first()?;
+    let max = (1usize << prefix) - 1;
+    let mut value = first as usize & max;
+    if value == max {
+        let mut shift = 0;
+        loop {
+            let (&octet, tail) = rest.split_first()?;
+            rest = tail;
+            value = value.checked_add(((octet & 0x7f) as usize).checked_shl(shift)?)?;
+            shift += 7;
+            if octet & 0x80 == 0 {
+                break;
+            }
+            if shift > 28 {
+                return None;
+            }
+        }
+    }
+    *block = rest;
+    Some(value)
+}
+
+/// Skips a string literal (RFC 7541, section 5.2), returning its coding.
+fn string(block: &mut &[u8]) -> Option<Source> {
+    let huffman = block.first()? & 0x80 != 0;
+    let len = integer(block, 7)?;
+    if block.len() < len {
+        return None;
+    }
+    *block = &block[len..];
+    Some(if huffman {
+        Source::Huffman
+    } else {
+        Source::Raw
+    })
+}
+
+#[derive(Clone, Serialize)]
+pub struct HpackFingerprint {
+    pub sha1: String,
+    pub text: String,
+}
+
+impl HpackFingerprint {
+    pub fn new(frames: &[Frame]) -> Option<Self> {
+        let frame = frames.iter().find_map(|frame| match frame {
+            Frame::Headers(frame) => Some(frame),
+            _ => None,
+        })?;
+        let table_size_updates = if frame.table_size_updates.is_empty() {
+            "-".to_string()
+        } else {
+            frame
+                .table_size_updates
+                .iter()
+                .map(|size| size.to_string())
+                .collect::<Vec<_>>()
+                .join(";")
+        };
+        let fields = frame
+            .headers
+            .iter()
+            .map(|header| {
+                let encoding = header.encoding?;
+                Some(format!("{}/{}", encoding.code(), header.name))
+            })
+            .collect::<Option<Vec<_>>>()?
+            .join(",");
+        let text = format!("{table_size_updates}|{fields}");
+        Some(Self {
+            sha1: sha1_smol::Sha1::from(&text).hexdigest(),
+            text,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use httlib_hpack::Decoder;
+
+    /// The HEADERS block sent by curl 7.88.1 (nghttp2 1.52.0) for
+    /// `curl --http2-prior-knowledge http://127.0.0.1/index.json`.
+    const CURL_BLOCK: &str =
+        "82048860d5485f2bf441ea86418b089d5c0b8170dc65c71c0f7a8825b650c3abbcf2e153032a2f2a";
+    /// Offsets in `CURL_BLOCK` at which a field representation starts.
+    const CURL_FIELD_OFFSETS: [usize; 6] = [0, 1, 11, 12, 25, 35];
+
+    fn codes(block: &str) -> Option<(Vec<u32>, Vec<String>)> {
+        let encoding = BlockEncoding::scan(&hex::decode(block).unwrap())?;
+        let codes = encoding.fields.iter().map(FieldEncoding::code).collect();
+        Some((encoding.table_size_updates, codes))
+    }
+
+    #[test]
+    fn scan_curl_block() {
+        let (updates, codes) = codes(CURL_BLOCK).unwrap();
+        assert!(updates.is_empty());
+        assert_eq!(codes, ["is", "nsh", "is", "lsh", "lsh", "lsr"]);
+    }
+
+    #[test]
+    fn fingerprint_curl_block() {
+        let block = hex::decode(CURL_BLOCK).unwrap();
+        let mut decoder = Decoder::default();
+        let frame = (0x4, 1, &block[..], 1, &mut decoder).try_into().unwrap();
+        let fingerprint = HpackFingerprint::new(&[Frame::Headers(frame)]).unwrap();
+        assert_eq!(
+            fingerprint.text,
+            "-|is/:method,nsh/:path,is/:scheme,lsh/:authority,lsh/user-agent,lsr/accept"
+        );
+    }
+
+    #[test]
+    fn encode_literals() {
+        let value = "v".repeat(300);
+        let mut block = Vec::new();
+        encode_literal(&mut block, b"x-test", value.as_bytes());
+        encode_literal(&mut block, b"accept", b"*/*");
+        assert_eq!(
+            block[..9],
+            [0x00, 0x06, b'x', b'-', b't', b'e', b's', b't', 0x7f]
+        );
+        assert_eq!(block[9..11], [0xad, 0x01]);
+        assert_eq!(codes(&hex::encode(&block)).unwrap().1, ["nrr", "nrr"]);
+
+        let mut headers = Vec::new();
+        Decoder::default().decode(&mut block, &mut headers).unwrap();
+        assert_eq!(headers[0].1, value.as_bytes());
+        assert_eq!(headers[1].0, b"accept");
+    }
+
+    #[test]
+    fn scan_rfc7541_examples() {
+        // Appendix C.2.2, C.2.3, C.3.1, C.3.2 and C.4.1.
+        for (block, expected) in [
+            ("040c2f73616d706c652f70617468", &["nsr"][..]),
+            ("100870617373776f726406736563726574", &["vrr"]),
+            (
+                "828684410f7777772e6578616d706c652e636f6d",
+                &["is", "is", "is", "lsr"],
+            ),
+            (
+                "828684be58086e6f2d6361636865",
+                &["is", "is", "is", "id", "lsr"],
+            ),
+            (
+                "828684418cf1e3c2e5f23a6ba0ab90f4ff",
+                &["is", "is", "is", "lsh"],
+            ),
+        ] {
+            assert_eq!(codes(block).unwrap().1, expected);
+        }
+
+        // A dynamic table size update to 4096 ahead of the fields.
+        let (updates, codes) = codes("3fe11f82").unwrap();
+        assert_eq!(updates, [4096]);
+        assert_eq!(codes, ["is"]);
+    }
+
+    #[test]
+    fn reject_truncated_block() {
+        let block = hex::decode(CURL_BLOCK).unwrap();
+        for len in 0..block.len() {
+            let encoding = BlockEncoding::scan(&block[..len]);
+            if let Some(offset) = CURL_FIELD_OFFSETS.iter().position(|&offset| offset == len) {
+                assert_eq!(encoding.unwrap().fields.len(), offset);
+            } else {
+                assert!(encoding.is_none(), "scanned {len} bytes");
+            }
+        }
+    }
+
+    #[test]
+    fn reject_malformed_block() {
+        for block in [
+            // An indexed field with index 0.
+            "80",
+            // An integer overflowing its continuation bytes.
+            "ffffffffffff7f",
+            // A string longer than the rest of the block.
+            "0408636f6e74656e74",
+            // A table size update that does not fit in 32 bits.
+            "3fffffffff0f",
+        ] {
+            assert!(codes(block).is_none(), "scanned {block}");
+        }
+    }
+
+    #[test]
+    fn corrupted_block_does_not_panic() {
+        let block = hex::decode(CURL_BLOCK).unwrap();
+        for offset in 0..block.len() {
+            for value in [0x00, 0x0f, 0x10, 0x3f, 0x7f, 0x80, 0xff] {
+                let mut block = block.clone();
+                block[offset] = value;
+                let _ = BlockEncoding::scan(&block);
+            }
+        }
+    }
+}
@@ -0,0 +1,714 @@
+use crate::http2::{Setting, SettingsFrame};
+use crate::inspect::Inspect;
+use base64::Engine;
+use serde_derive::Serialize;
+use std::collections::VecDeque;
+use std::pin::Pin;
+use std::sync::Mutex;
+use std::task;
+use std::task::Poll;
+use std::{io::IoSlice, sync::Arc};
+use tokio::io::{self, AsyncRead, AsyncWrite, ReadBuf};
+
+/// Request heads larger than this are not inspected.
+const MAX_HEAD_LEN: usize = 64 * 1024;
+
+/// Limit on the request heads waiting to be taken by the service. Past it, the
+/// connection is no longer inspected, since dropping heads would hand later
+/// requests the wrong ones.
+const MAX_QUEUED_REQUESTS: usize = 256;
+
+/// Headers carrying credentials. Only their length is kept, since the request
+/// head ends up in reports that may be cached or shared.
+const SENSITIVE_HEADERS: &[&str] = &[
+    "authorization",
+    "proxy-authorization",
+    "cookie",
+    "x-api-key",
+    "x-auth-token",
+    "x-csrf-token",
+];
+
+pin_project_lite::pin_project! {
+    /// Captures the raw head of every HTTP/1 request on a connection. HTTP/2
+    /// connections are passed through untouched.
+    pub struct Http1Inspector<S> {
+        #[pin]
+        inner: S,
+
+        buf: Vec<u8>,
+        state: State,
+        requests: Arc<Mutex<VecDeque<Http1Request>>>,
+    }
+}
+
+/// Where the inspector is in the request stream: request bodies are skipped
+/// so that the next head can be found. While waiting for a head, the number of
+/// buffered bytes already searched for its end is kept.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Head(usize),
+    Body(usize),
+    ChunkSize,
+    ChunkData(usize),
+    ChunkEnd,
+    Trailers,
+    Done,
+}
+
+impl<S> Http1Inspector<S> {
+    pub fn new(inner: S) -> Self {
+        Self {
+            inner,
+            buf: Vec::new(),
+            state: State::Head(0),
+            requests: Arc::new(Mutex::new(VecDeque::new())),
+        }
+    }
+}
+
+impl<S> Inspect for Http1Inspector<S> {
+    type Inner = S;
+    type Capture = Arc<Mutex<VecDeque<Http1Request>>>;
+
+    fn get_ref(&self) -> &S {
+        &self.inner
+    }
+
+    fn capture(&self) -> &Arc<Mutex<VecDeque<Http1Request>>> {
+        &self.requests
+    }
+}
+
+impl<S: AsyncRead> AsyncRead for Http1Inspector<S> {
+    #[inline]
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut task::Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let len = buf.filled().len();
+        let me = self.project();
+        let poll = me.inner.poll_read(cx, buf);
+
+        if *me.state != State::Done {
+            me.buf.extend(&buf.filled()[len..]);
+            inspect(me.state, me.buf, &mut me.requests.lock().unwrap());
+            if *me.state == State::Done {
+                *me.buf = Vec::new();
+            }
+        }
+
+        poll
+    }
+}
+
+impl<S: AsyncWrite> AsyncWrite for Http1Inspector<S> {
+    #[inline]
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut task::Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        self.project().inner.poll_write(cx, buf)
+    }
+
+    #[inline]
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut task::Context<'_>,
+        bufs: &[IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        self.project().inner.poll_write_vectored(cx, bufs)
+    }
+
+    #[inline]
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<io::Result<()>> {
+        self.project().inner.poll_flush(cx)
+    }
+
+    #[inline]
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<io::Result<()>> {
+        self.project().inner.poll_shutdown(cx)
+    }
+
+    #[inline]
+    fn is_write_vectored(&self) -> bool {
+        self.inner.is_write_vectored()
+    }
+}
+
+/// Queues the request heads completed by buffered data.
+fn inspect(state: &mut State, buf: &mut Vec<u8>, requests: &mut VecDeque<Http1Request>) {
+    while let Some(request) = advance(state, buf) {
+        if requests.len() >= MAX_QUEUED_REQUESTS {
+            *state = State::Done;
+            return;
+        }
+        requests.push_back(request);
+    }
+}
+
+/// Consumes buffered data until the next request head is complete, or until
+/// more data is needed.
+fn advance(state: &mut State, buf: &mut Vec<u8>) -> Option<Http1Request> {
+    loop {
+        match *state {
+            State::Head(scanned) => {
+                // Empty lines before the request line are ignored (RFC 9112,
+                // section 2.2).
+                let start = buf
+                    .iter()
+                    .position(|&c| c != b'\r' && c != b'\n')
+                    .unwrap_or(buf.len());
+                buf.drain(..start);
+                let mut scanned = scanned.saturating_sub(start);
+                let Some(end) = head_end(buf, &mut scanned) else {
+                    *state = if buf.len() > MAX_HEAD_LEN {
+                        State::Done
+                    } else {
+                        State::Head(scanned)
+                    };
+                    return None;
+                };
+                let head = buf.drain(..end).collect::<Vec<_>>();
+                let Some(request) = Http1Request::parse(&head) else {
+                    *state = State::Done;
+                    return None;
+                };
+                if request.version.starts_with("HTTP/2") {
+                    *state = State::Done;
+                    return None;
+                }
+                *state = if request.is_chunked() {
+                    State::ChunkSize
+                } else {
+                    match request.content_length() {
+                        Some(len) if len > 0 => State::Body(len),
+                        _ => State::Head(0),
+                    }
+                };
+                return Some(request);
+            }
+            State::Body(len) | State::ChunkData(len) => {
+                let skip = len.min(buf.len());
+                buf.drain(..skip);
+                if skip < len {
+                    *state = match *state {
+                        State::Body(_) => State::Body(len - skip),
+                        _ => State::ChunkData(len - skip),
+                    };
+                    return None;
+                }
+                *state = match *state {
+                    State::Body(_) => State::Head(0),
+                    _ => State::ChunkEnd,
+                };
+            }
+            State::ChunkSize | State::ChunkEnd | State::Trailers => {
+                let line = buf.iter().position(|&c| c == b'\n')?;
+                let text = String::from_utf8_lossy(&buf[..line]).trim().to_string();
+                buf.drain(..=line);
+                *state = match *state {
+                    State::ChunkSize => {
+                        let size = text.split(';').next().unwrap_or_default().trim();
+                        match usize::from_str_radix(size, 16) {
+                            Ok(0) => State::Trailers,
+                            Ok(size) => State::ChunkData(size),
+                            Err(_) => State::Done,
+                        }
+                    }
+                    State::ChunkEnd => State::ChunkSize,
+                    _ if text.is_empty() => State::Head(0),
+                    _ => State::Trailers,
+                };
+            }
+            State::Done => return None,
+        }
+    }
+}
+
+/// Returns the length of the request head, including the empty line. The
+/// search resumes at `scanned`, which is then moved up to where the next one
+/// has to start: the last two bytes may begin a line ending cut short.
+fn head_end(buf: &[u8], scanned: &mut usize) -> Option<usize> {
+    let from = (*scanned).min(buf.len());
+    *scanned = buf.len().saturating_sub(2);
+    buf[from..].iter().enumerate().find_map(|(i, &c)| {
+        let i = from + i;
+        if c != b'\n' {
+            return None;
+        }
+        match &buf[i + 1..] {
+            [b'\n', ..] => Some(i + 2),
+            [b'\r', b'\n', ..] => Some(i + 3),
+            _ => None,
+        }
+    })
+}
+
+/// Returns what to report instead of the value of a header carrying
+/// credentials, or `None` for any other header.
+pub(crate) fn redact(name: &str, value: &str) -> Option<String> {
+    SENSITIVE_HEADERS
+        .iter()
+        .any(|sensitive| name.eq_ignore_ascii_case(sensitive))
+        .then(|| format!("[redacted, {} bytes]", value.len()))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LineEnding {
+    Crlf,
+    Lf,
+    Mixed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HeaderCasing {
+    Lower,
+    Title,
+    Mixed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Http1Header {
+    pub name: String,
+    pub value: String,
+}
+
+/// A request head as sent by the client, with header names in their original
+/// order and casing.
+#[derive(Debug, Clone, Serialize)]
+pub struct Http1Request {
+    pub request_line: String,
+    pub method: String,
+    pub target: String,
+    pub version: String,
+    pub headers: Vec<Http1Header>,
+    pub line_ending: LineEnding,
+    pub raw: String,
+}
+
+impl Http1Request {
+    fn parse(head: &[u8]) -> Option<Self> {
+        let head = String::from_utf8_lossy(head);
+        let mut crlf = 0;
+        let mut lf = 0;
+        let mut lines = head.split_inclusive('\n').inspect(|line| {
+            if line.ends_with("\r\n") {
+                crlf += 1;
+            } else {
+                lf += 1;
+            }
+        });
+        let content = |line: &str| line.trim_end_matches('\n').trim_end_matches('\r').len();
+
+        let line = lines.next()?;
+        let request_line = line[..content(line)].to_string();
+        let mut parts = request_line.split(' ');
+        let method = parts
+            .next()
+            .filter(|method| !method.is_empty())?
+            .to_string();
+        let target = parts.next()?.to_string();
+        let version = parts.next().filter(|v| v.starts_with("HTTP/"))?.to_string();
+        if parts.next().is_some() {
+            return None;
+        }
+
+        // The raw head is rebuilt line by line, so that redacted values do
+        // not show up in it either.
+        let mut raw = line.to_string();
+        let mut headers = Vec::new();
+        for line in lines.by_ref() {
+            let (field, line_ending) = line.split_at(content(line));
+            if field.is_empty() {
+                raw.push_str(line);
+                break;
+            }
+            let (name, value) = field.split_once(':')?;
+            if let Some(redacted) = redact(name, value.trim()) {
+                let space = &value[..value.len() - value.trim_start().len()];
+                raw.push_str(&format!("{name}:{space}{redacted}{line_ending}"));
+                headers.push(Http1Header {
+                    name: name.to_string(),
+                    value: redacted,
+                });
+            } else {
+                raw.push_str(line);
+                headers.push(Http1Header {
+                    name: name.to_string(),
+                    value: value.trim().to_string(),
+                });
+            }
+        }
+
+        let line_ending = match (crlf, lf) {
+            (_, 0) => LineEnding::Crlf,
+            (0, _) => LineEnding::Lf,
+            _ => LineEnding::Mixed,
+        };
+        Some(Self {
+            request_line,
+            method,
+            target,
+            version,
+            headers,
+            line_ending,
+            raw,
+        })
+    }
+
+    /// Returns the values of a header, matched case-insensitively.
+    fn values<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a str> {
+        self.headers
+            .iter()
+            .filter(move |header| header.name.eq_ignore_ascii_case(name))
+            .map(|header| header.value.as_str())
+    }
+
+    fn tokens(&self, name: &str) -> Vec<String> {
+        self.values(name)
+            .flat_map(|value| value.split(','))
+            .map(|token| token.trim().to_ascii_lowercase())
+            .filter(|token| !token.is_empty())
+            .collect()
+    }
+
+    fn is_chunked(&self) -> bool {
+        self.tokens("transfer-encoding")
+            .last()
+            .is_some_and(|coding| coding == "chunked")
+    }
+
+    fn content_length(&self) -> Option<usize> {
+        self.values("content-length").next()?.parse().ok()
+    }
+
+    /// The tokens of the Connection header, in the order they were sent.
+    pub fn connection(&self) -> Vec<String> {
+        self.tokens("connection")
+    }
+
+    /// Whether the client expects the connection to persist (RFC 9112,
+    /// section 9.3).
+    pub fn keep_alive(&self) -> bool {
+        let connection = self.connection();
+        if connection.iter().any(|token| token == "close") {
+            false
+        } else if self.version == "HTTP/1.0" {
+            connection.iter().any(|token| token == "keep-alive")
+        } else {
+            true
+        }
+    }
+
+    /// Decodes the HTTP2-Settings header of an `Upgrade: h2c` request (RFC
+    /// 7540, section 3.2.1).
+    pub fn h2c_upgrade_settings(&self) -> Option<H2cUpgradeSettings> {
+        if !self
+            .tokens("upgrade")
+            .iter()
+            .any(|protocol| protocol == "h2c")
+        {
+            return None;
+        }
+        let value = self.values("http2-settings").next()?;
+        let payload = base64::engine::general_purpose::URL_SAFE_NO_PAD
+            .decode(value.trim_end_matches('='))
+            .ok()?;
+        if payload.len() % 6 != 0 {
+            return None;
+        }
+        let frame = SettingsFrame::try_from((0, payload.as_slice())).ok()?;
+        Some(H2cUpgradeSettings::new(frame.settings))
+    }
+
+    /// Whether the connection can be switched to h2c for this request: it
+    /// has a single HTTP2-Settings header that decodes, named in Connection
+    /// along with Upgrade, and no body to carry over to HTTP/2.
+    pub fn accepts_h2c_upgrade(&self) -> bool {
+        let connection = self.connection();
+        self.version == "HTTP/1.1"
+            && self.h2c_upgrade_settings().is_some()
+            && self.values("http2-settings").count() == 1
+            && ["upgrade", "http2-settings"]
+                .iter()
+                .all(|token| connection.iter().any(|t| t == token))
+            && !self.is_chunked()
+            && self.content_length().unwrap_or(0) == 0
+    }
+
+    pub fn header_casing(&self) -> HeaderCasing {
+        let is_title = |name: &str| {
+            name.split('-').all(|word| {
+                let mut chars = word.chars();
+                chars.next().map_or(true, |c| !c.is_ascii_lowercase())
+                    && chars.all(|c| !c.is_ascii_uppercase())
+            })
+        };
+        if self
+            .headers
+            .iter()
+            .all(|header| !header.name.bytes().any(|c| c.is_ascii_uppercase()))
+        {
+            HeaderCasing::Lower
+        } else if self.headers.iter().all(|header| is_title(&header.name)) {
+            HeaderCasing::Title
+        } else {
+            HeaderCasing::Mixed
+        }
+    }
+}
+
+#[derive(Clone, Serialize)]
+pub struct Http1Fingerprint {
+    pub sha1: String,
+    pub text: String,
+}
+
+impl Http1Fingerprint {
+    pub fn new(request: &Http1Request) -> Self {
+        let version = request.version.trim_start_matches("HTTP/");
+        let line_ending = match request.line_ending {
+            LineEnding::Crlf => "crlf",
+            LineEnding::Lf => "lf",
+            LineEnding::Mixed => "mixed",
+        };
+        let connection = request.connection();
+        let connection = if connection.is_empty() {
+            "-".to_string()
+        } else {
+            connection.join(";")
+        };
+        let headers = request
+            .headers
+            .iter()
+            .map(|header| header.name.as_str())
+            .collect::<Vec<_>>()
+            .join(",");
+        let text = format!("{version}|{line_ending}|{connection}|{headers}");
+        Self {
+            sha1: sha1_smol::Sha1::from(&text).hexdigest(),
+            text,
+        }
+    }
+}
+
+/// The SETTINGS a client advertised in the HTTP2-Settings header of an
+/// `Upgrade: h2c` request, which stand in for the SETTINGS frame it would
+/// otherwise send first.
+#[derive(Clone, Serialize)]
+pub struct H2cUpgradeSettings {
+    pub text: String,
+    pub settings: Vec<Setting>,
+}
+
+impl H2cUpgradeSettings {
+    fn new(settings: Vec<Setting>) -> Self {
+        let text = settings
+            .iter()
+            .map(|setting| format!("{}:{}", setting.id, setting.value))
+            .collect::<Vec<_>>()
+            .join(";");
+        Self { text, settings }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The request head sent by curl 7.88.1 for `curl http://127.0.0.1:8080/`.
+    const CURL_HEAD: &[u8] =
+        b"GET / HTTP/1.1\r\nHost: 127.0.0.1:8080\r\nUser-Agent: curl/7.88.1\r\nAccept: */*\r\n\r\n";
+
+    /// Feeds `data` to the inspector state machine one byte at a time.
+    fn feed(data: &[u8]) -> (VecDeque<Http1Request>, State) {
+        let mut state = State::Head(0);
+        let mut buf = Vec::new();
+        let mut requests = VecDeque::new();
+        for &c in data {
+            buf.push(c);
+            inspect(&mut state, &mut buf, &mut requests);
+        }
+        (requests, state)
+    }
+
+    #[test]
+    fn parse_curl_head() {
+        let request = Http1Request::parse(CURL_HEAD).unwrap();
+        assert_eq!(request.request_line, "GET / HTTP/1.1");
+        assert_eq!(request.version, "HTTP/1.1");
+        assert_eq!(request.line_ending, LineEnding::Crlf);
+        assert_eq!(request.header_casing(), HeaderCasing::Title);
+        assert!(request.keep_alive());
+        assert_eq!(
+            Http1Fingerprint::new(&request).text,
+            "1.1|crlf|-|Host,User-Agent,Accept"
+        );
+    }
+
+    #[test]
+    fn parse_head_variants() {
+        let request =
+            Http1Request::parse(b"GET / HTTP/1.0\nhost: a\nconnection: Keep-Alive\n\n").unwrap();
+        assert_eq!(request.line_ending, LineEnding::Lf);
+        assert_eq!(request.header_casing(), HeaderCasing::Lower);
+        assert!(request.keep_alive());
+        assert_eq!(
+            Http1Fingerprint::new(&request).text,
+            "1.0|lf|keep-alive|host,connection"
+        );
+
+        let request =
+            Http1Request::parse(b"GET / HTTP/1.1\r\nX-ID: 1\nConnection: close\r\n\r\n").unwrap();
+        assert_eq!(request.line_ending, LineEnding::Mixed);
+        assert_eq!(request.header_casing(), HeaderCasing::Mixed);
+        assert!(!request.keep_alive());
+    }
+
+    #[test]
+    fn redact_credentials() {
+        let head =
+            b"GET / HTTP/1.1\r\nHost: a\r\nCookie: id=secret\r\nauthorization:Basic dTpw\n\r\n";
+        let request = Http1Request::parse(head).unwrap();
+        assert_eq!(
+            request.raw,
+            "GET / HTTP/1.1\r\nHost: a\r\nCookie: [redacted, 9 bytes]\r\n\
+             authorization:[redacted, 10 bytes]\n\r\n"
+        );
+        let values = request
+            .headers
+            .iter()
+            .map(|header| header.value.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(values, ["a", "[redacted, 9 bytes]", "[redacted, 10 bytes]"]);
+        assert_eq!(request.line_ending, LineEnding::Mixed);
+        assert_eq!(
+            Http1Fingerprint::new(&request).text,
+            "1.1|mixed|-|Host,Cookie,authorization"
+        );
+    }
+
+    #[test]
+    fn reject_malformed_head() {
+        for head in [
+            &b"\r\n\r\n"[..],
+            b" / HTTP/1.1\r\n\r\n",
+            b"GET /\r\n\r\n",
+            b"GET / HTTP/1.1 extra\r\n\r\n",
+            b"GET / SPDY/3\r\n\r\n",
+            b"GET / HTTP/1.1\r\nHost\r\n\r\n",
+        ] {
+            assert!(Http1Request::parse(head).is_none());
+        }
+        // Invalid UTF-8 is replaced rather than rejected.
+        let request = Http1Request::parse(b"GET /\xff HTTP/1.1\r\n\r\n").unwrap();
+        assert_eq!(request.target, "/\u{fffd}");
+    }
+
+    #[test]
+    fn wait_for_complete_head() {
+        for len in 0..CURL_HEAD.len() {
+            assert_eq!(head_end(&CURL_HEAD[..len], &mut 0), None);
+            let (requests, state) = feed(&CURL_HEAD[..len]);
+            assert!(requests.is_empty());
+            assert_eq!(state, State::Head(len.saturating_sub(2)));
+        }
+        assert_eq!(head_end(CURL_HEAD, &mut 0), Some(CURL_HEAD.len()));
+    }
+
+    #[test]
+    fn skip_request_bodies() {
+        let data = [
+            &b"\r\nPOST /a HTTP/1.1\r\nContent-Length: 4\r\n\r\nbody"[..],
+            b"POST /b HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n",
+            b"3;ext=1\r\nabc\r\n0\r\nTrailer: x\r\n\r\n",
+            CURL_HEAD,
+        ]
+        .concat();
+        let (requests, state) = feed(&data);
+        let targets = requests
+            .iter()
+            .map(|request| request.target.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(targets, ["/a", "/b", "/"]);
+        assert_eq!(state, State::Head(0));
+    }
+
+    #[test]
+    fn stop_on_malformed_input() {
+        let (requests, state) =
+            feed(b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\nzz\r\n");
+        assert_eq!(requests.len(), 1);
+        assert_eq!(state, State::Done);
+
+        let (requests, state) = feed(b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n");
+        assert!(requests.is_empty());
+        assert_eq!(state, State::Done);
+
+        let (requests, state) = feed(b"NOT A REQUEST\r\n\r\n");
+        assert!(requests.is_empty());
+        assert_eq!(state, State::Done);
+
+        let head = [&b"GET / HTTP/1.1\r\nX: "[..], &[b'a'; MAX_HEAD_LEN]].concat();
+        let mut state = State::Head(0);
+        let mut buf = head;
+        assert!(advance(&mut state, &mut buf).is_none());
+        assert_eq!(state, State::Done);
+    }
+
+    #[test]
+    fn stop_when_too_many_requests_are_queued() {
+        let data = CURL_HEAD.repeat(MAX_QUEUED_REQUESTS + 1);
+        let mut state = State::Head(0);
+        let mut buf = data;
+        let mut requests = VecDeque::new();
+        inspect(&mut state, &mut buf, &mut requests);
+        assert_eq!(requests.len(), MAX_QUEUED_REQUESTS);
+        assert_eq!(state, State::Done);
+    }
+
+    #[test]
+    fn decode_h2c_upgrade_settings() {
+        // SETTINGS_MAX_CONCURRENT_STREAMS = 100, SETTINGS_INITIAL_WINDOW_SIZE
+        // = 33554432 and SETTINGS_ENABLE_PUSH = 0, as sent by curl.
+        let head = b"GET / HTTP/1.1\r\nConnection: Upgrade, HTTP2-Settings\r\n\
+                     Upgrade: h2c\r\nHTTP2-Settings: AAMAAABkAAQCAAAAAAIAAAAA\r\n\r\n";
+        let request = Http1Request::parse(head).unwrap();
+        assert_eq!(
+            request.h2c_upgrade_settings().unwrap().text,
+            "3:100;4:33554432;2:0"
+        );
+
+        for value in ["AAMAAABkAAQCAAAAAAIAAAA", "!!!!"] {
+            let head = format!("GET / HTTP/1.1\r\nUpgrade: h2c\r\nHTTP2-Settings: {value}\r\n\r\n");
+            let request = Http1Request::parse(head.as_bytes()).unwrap();
+            assert!(request.h2c_upgrade_settings().is_none());
+        }
+    }
+
+    #[test]
+    fn accept_h2c_upgrade_without_body() {
+        let upgrade = "GET / HTTP/1.1\r\nConnection: Upgrade, HTTP2-Settings\r\n\
+                       Upgrade: h2c\r\nHTTP2-Settings: AAMAAABkAAQCAAAAAAIAAAAA\r\n";
+        let accepts = |extra: &str| {
+            let head = format!("{upgrade}{extra}\r\n");
+            Http1Request::parse(head.as_bytes())
+                .unwrap()
+                .accepts_h2c_upgrade()
+        };
+        assert!(accepts(""));
+        assert!(accepts("Content-Length: 0\r\n"));
+        assert!(!accepts("Content-Length: 5\r\n"));
+        assert!(!accepts("Transfer-Encoding: chunked\r\n"));
+        assert!(!accepts("HTTP2-Settings: AAMAAABkAAQCAAAAAAIAAAAA\r\n"));
+
+        let head = b"GET / HTTP/1.1\r\nConnection: Upgrade\r\nUpgrade: h2c\r\n\
+                     HTTP2-Settings: AAMAAABkAAQCAAAAAAIAAAAA\r\n\r\n";
+        assert!(!Http1Request::parse(head).unwrap().accepts_h2c_upgrade());
+    }
+}
@@ -1,42 +1,173 @@
-use crate::tls::TlsInspctor;
+use crate::hpack::{BlockEncoding, FieldEncoding};
+use crate::iana;
+use crate::inspect::Inspect;
+use crate::ja3::is_not_grease;
+use crate::priority_tree::PriorityTree;
 use httlib_hpack::Decoder;
 use serde::ser::SerializeMap;
 use serde_derive::Serialize;
+use std::collections::VecDeque;
 use std::pin::Pin;
 use std::sync::Mutex;
 use std::task;
 use std::task::Poll;
 use std::{io::IoSlice, sync::Arc};
 use tokio::io::{self, AsyncRead, AsyncWrite, ReadBuf};
-use tokio_rustls::server::TlsStream;
 
-const HTTP2_PREFACE: &[u8] = b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n";
+pub(crate) const HTTP2_PREFACE: &[u8] = b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n";
+
+/// Limit on the PRIORITY_UPDATE frames kept per connection.
+const MAX_PRIORITY_UPDATES: usize = 64;
+
+/// Limit on the frames kept before a HEADERS frame. Any more are dropped.
+const MAX_PENDING_FRAMES: usize = 64;
+
+/// Limit on the requests waiting to be taken by the service. Streams that are
+/// never handed to it without being reset stay queued, so the oldest ones are
+/// dropped.
+const MAX_QUEUED_REQUESTS: usize = 256;
+
+/// Limit on the size of a header block reassembled from CONTINUATION frames.
+/// A larger block is dropped without being decoded.
+const MAX_HEADER_BLOCK_LEN: usize = 64 * 1024;
 
 pin_project_lite::pin_project! {
-    pub struct Http2Inspector {
+    pub struct Http2Inspector<S> {
         #[pin]
-        inner: TlsStream<TlsInspctor>,
+        inner: S,
 
         buf: Vec<u8>,
-        frames: Arc<Mutex<Vec<Frame>>>
+        frames: Arc<Mutex<Http2Frames>>,
+        header_blocks: HeaderBlocks,
+        sent: SentFrames,
     }
 }
 
-impl Http2Inspector {
-    pub fn new(inner: TlsStream<TlsInspctor>) -> Self {
+impl<S> Http2Inspector<S> {
+    pub fn new(inner: S) -> Self {
         Self {
             inner,
             buf: Vec::new(),
-            frames: Arc::new(Mutex::new(Vec::new())),
+            frames: Arc::new(Mutex::new(Http2Frames::default())),
+            header_blocks: HeaderBlocks::default(),
+            sent: SentFrames::default(),
         }
     }
+}
+
+impl<S> Inspect for Http2Inspector<S> {
+    type Inner = S;
+    type Capture = Arc<Mutex<Http2Frames>>;
+
+    fn get_ref(&self) -> &S {
+        &self.inner
+    }
+
+    fn capture(&self) -> &Arc<Mutex<Http2Frames>> {
+        &self.frames
+    }
+}
+
+/// The frames received on a connection, split into the connection preface and
+/// the frames leading up to each request.
+#[derive(Default)]
+pub struct Http2Frames {
+    /// The frames up to and including the first HEADERS frame, of which at
+    /// most `MAX_PENDING_FRAMES` precede it.
+    pub preface: Vec<Frame>,
+    requests: VecDeque<RequestFrames>,
+    pending: Vec<Frame>,
+    last_stream_id: u32,
+    pub priority_tree: PriorityTree,
+    /// PRIORITY_UPDATE frames received so far, which mostly follow the
+    /// HEADERS frame of the stream they reprioritize.
+    pub priority_updates: Vec<PriorityUpdateFrame>,
+}
+
+/// The frames preceding a request, ending with the HEADERS frame opening its
+/// stream.
+pub struct RequestFrames {
+    pub stream_id: u32,
+    pub frames: Vec<Frame>,
+}
 
-    pub fn frames(&self) -> Arc<Mutex<Vec<Frame>>> {
-        self.frames.clone()
+impl RequestFrames {
+    pub fn headers(&self) -> Option<&HeadersFrame> {
+        match self.frames.last() {
+            Some(Frame::Headers(frame)) => Some(frame),
+            _ => None,
+        }
     }
 }
 
-impl AsyncRead for Http2Inspector {
+impl Http2Frames {
+    fn push(&mut self, frame: Frame) {
+        match &frame {
+            Frame::Headers(frame) => self.priority_tree.headers(frame),
+            Frame::Priority(frame) => self
+                .priority_tree
+                .prioritize(frame.stream_id, &frame.priority),
+            Frame::PriorityUpdate(frame) if self.priority_updates.len() < MAX_PRIORITY_UPDATES => {
+                self.priority_updates.push(frame.clone())
+            }
+            Frame::RstStream(frame) => self.reset(frame.stream_id),
+            _ => (),
+        }
+        match frame {
+            Frame::Headers(headers) if headers.stream_id > self.last_stream_id => {
+                let stream_id = headers.stream_id;
+                self.last_stream_id = stream_id;
+                self.pending.push(Frame::Headers(headers));
+                let frames = std::mem::take(&mut self.pending);
+                if self.preface.is_empty() {
+                    self.preface = frames.clone();
+                }
+                if self.requests.len() >= MAX_QUEUED_REQUESTS {
+                    self.requests.pop_front();
+                }
+                self.requests.push_back(RequestFrames { stream_id, frames });
+            }
+            _ if self.pending.len() >= MAX_PENDING_FRAMES => (),
+            // Before the first request everything is kept for the preface;
+            // afterwards only the frames that shape the next request are.
+            _ if self.preface.is_empty() => self.pending.push(frame),
+            Frame::Settings(_)
+            | Frame::WindowUpdate(_)
+            | Frame::Priority(_)
+            | Frame::PriorityUpdate(_) => self.pending.push(frame),
+            _ => (),
+        }
+    }
+
+    /// Forgets a stream reset by either side, which is not handed to the
+    /// service if it was not already.
+    fn reset(&mut self, stream_id: u32) {
+        self.requests
+            .retain(|request| request.stream_id != stream_id);
+    }
+
+    /// Takes the frames of the oldest queued request with the given method and
+    /// path. The service is handed streams in the order they were opened, and
+    /// those it never gets are reset, so this is the stream being served.
+    pub fn take_request(&mut self, method: &str, path: &str) -> Option<RequestFrames> {
+        let matches = |request: &RequestFrames| {
+            request.headers().is_some_and(|headers| {
+                let value = |name| {
+                    headers
+                        .headers
+                        .iter()
+                        .find(|header| header.name == name)
+                        .map(|header| header.value.as_str())
+                };
+                value(":method") == Some(method) && value(":path") == Some(path)
+            })
+        };
+        let index = self.requests.iter().position(matches)?;
+        self.requests.remove(index)
+    }
+}
+
+impl<S: AsyncRead> AsyncRead for Http2Inspector<S> {
     #[inline]
     fn poll_read(
         self: Pin<&mut Self>,
@@ -48,15 +179,11 @@ impl AsyncRead for Http2Inspector {
         let poll = me.inner.poll_read(cx, buf);
 
         let plen = HTTP2_PREFACE.len();
-        let not_http2 = me.buf.len() >= plen && !me.buf.starts_with(HTTP2_PREFACE);
-        if !not_http2 {
+        if may_be_http2(me.buf) {
             me.buf.extend(&buf.filled()[len..]);
             let mut frames = me.frames.lock().unwrap();
             while me.buf.len() > plen {
-                if matches!(frames.last(), Some(Frame::Headers(_))) {
-                    break;
-                }
-                let (frame_len, frame) = parse_frame(&me.buf[plen..]);
+                let (frame_len, frame) = parse_frame(&me.buf[plen..], me.header_blocks);
                 if frame_len > 0 {
                     me.buf.drain(plen..plen + frame_len);
                     if let Some(frame) = frame {
@@ -72,14 +199,22 @@ impl AsyncRead for Http2Inspector {
     }
 }
 
-impl AsyncWrite for Http2Inspector {
+impl<S: AsyncWrite> AsyncWrite for Http2Inspector<S> {
     #[inline]
     fn poll_write(
         self: Pin<&mut Self>,
         cx: &mut task::Context<'_>,
         buf: &[u8],
     ) -> Poll<io::Result<usize>> {
-        self.project().inner.poll_write(cx, buf)
+        let me = self.project();
+        let poll = me.inner.poll_write(cx, buf);
+        if let Poll::Ready(Ok(written)) = poll {
+            if may_be_http2(me.buf) {
+                me.sent
+                    .push(&buf[..written], &mut me.frames.lock().unwrap());
+            }
+        }
+        poll
     }
 
     #[inline]
@@ -88,12 +223,24 @@ impl AsyncWrite for Http2Inspector {
         cx: &mut task::Context<'_>,
         bufs: &[IoSlice<'_>],
     ) -> Poll<io::Result<usize>> {
-        self.project().inner.poll_write_vectored(cx, bufs)
+        let me = self.project();
+        let poll = me.inner.poll_write_vectored(cx, bufs);
+        if let Poll::Ready(Ok(mut written)) = poll {
+            if may_be_http2(me.buf) {
+                let mut frames = me.frames.lock().unwrap();
+                for buf in bufs {
+                    let len = written.min(buf.len());
+                    me.sent.push(&buf[..len], &mut frames);
+                    written -= len;
+                }
+            }
+        }
+        poll
     }
 
     #[inline]
-    fn poll_flush(self: Pin<&mut Self>, _cx: &mut task::Context<'_>) -> Poll<io::Result<()>> {
-        Poll::Ready(Ok(()))
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<io::Result<()>> {
+        self.project().inner.poll_flush(cx)
     }
 
     #[inline]
@@ -107,9 +254,46 @@ impl AsyncWrite for Http2Inspector {
     }
 }
 
-const FRAME_HEADER_LEN: usize = 9;
+/// Whether what the client has sent so far may be the HTTP/2 connection
+/// preface.
+fn may_be_http2(buf: &[u8]) -> bool {
+    buf.len() < HTTP2_PREFACE.len() || buf.starts_with(HTTP2_PREFACE)
+}
 
-fn parse_frame(data: &[u8]) -> (usize, Option<Frame>) {
+/// Follows the frames written by the server, only to learn which streams it
+/// refuses or resets.
+#[derive(Default)]
+struct SentFrames {
+    header: Vec<u8>,
+    remaining: usize,
+}
+
+impl SentFrames {
+    fn push(&mut self, mut data: &[u8], frames: &mut Http2Frames) {
+        while !data.is_empty() {
+            if self.remaining > 0 {
+                let skip = self.remaining.min(data.len());
+                self.remaining -= skip;
+                data = &data[skip..];
+                continue;
+            }
+            let take = (FRAME_HEADER_LEN - self.header.len()).min(data.len());
+            self.header.extend_from_slice(&data[..take]);
+            data = &data[take..];
+            if let [l0, l1, l2, ty, _, s0, s1, s2, s3] = self.header[..] {
+                if ty == FRAME_TYPE_RST_STREAM {
+                    frames.reset(u32::from_be_bytes([s0 & 0x7f, s1, s2, s3]));
+                }
+                self.remaining = u32::from_be_bytes([0, l0, l1, l2]) as usize;
+                self.header.clear();
+            }
+        }
+    }
+}
+
+pub(crate) const FRAME_HEADER_LEN: usize = 9;
+
+fn parse_frame(data: &[u8], header_blocks: &mut HeaderBlocks) -> (usize, Option<Frame>) {
     if data.len() < FRAME_HEADER_LEN {
         return (0, None);
     }
@@ -122,89 +306,267 @@ fn parse_frame(data: &[u8]) -> (usize, Option<Frame>) {
     if payload.len() < length {
         return (0, None);
     }
-    let frame = (ty, flags, stream_id, &payload[..length]).try_into().ok();
+    let payload = &payload[..length];
+    let frame = match ty {
+        FRAME_TYPE_HEADERS | FRAME_TYPE_CONTINUATION => {
+            header_blocks.push(ty, flags, stream_id, payload)
+        }
+        _ => (ty, flags, stream_id, payload).try_into().ok(),
+    };
     (FRAME_HEADER_LEN + length, frame)
 }
 
-#[derive(Debug, Serialize)]
+/// Reassembles header blocks split over CONTINUATION frames and decodes them
+/// with the connection's HPACK decoder.
+#[derive(Default)]
+struct HeaderBlocks {
+    decoder: Decoder<'static>,
+    pending: Option<PendingHeaders>,
+}
+
+struct PendingHeaders {
+    flags: u8,
+    stream_id: u32,
+    block: Vec<u8>,
+    fragments: usize,
+    /// Set once the block has grown past `MAX_HEADER_BLOCK_LEN`, after which
+    /// the rest of it is skipped.
+    overflowed: bool,
+}
+
+impl HeaderBlocks {
+    /// Takes a HEADERS or CONTINUATION frame and returns the HEADERS frame once
+    /// its header block is complete. A CONTINUATION frame that does not
+    /// continue a pending block is returned as is.
+    fn push(&mut self, ty: u8, flags: u8, stream_id: u32, payload: &[u8]) -> Option<Frame> {
+        let mut pending = match (ty, self.pending.take()) {
+            (FRAME_TYPE_HEADERS, _) => PendingHeaders {
+                flags,
+                stream_id,
+                block: unpad(flags, payload).ok()?.to_vec(),
+                fragments: 1,
+                overflowed: false,
+            },
+            (_, Some(mut pending)) if pending.stream_id == stream_id => {
+                if !pending.overflowed {
+                    pending.block.extend_from_slice(payload);
+                }
+                pending.fragments += 1;
+                pending
+            }
+            (_, pending) => {
+                self.pending = pending;
+                return (ty, flags, stream_id, payload).try_into().ok();
+            }
+        };
+        if pending.block.len() > MAX_HEADER_BLOCK_LEN {
+            pending.block = Vec::new();
+            pending.overflowed = true;
+        }
+        if flags & FLAG_END_HEADERS == 0 {
+            self.pending = Some(pending);
+            return None;
+        }
+        if pending.overflowed {
+            return None;
+        }
+        (
+            pending.flags,
+            pending.stream_id,
+            &pending.block[..],
+            pending.fragments,
+            &mut self.decoder,
+        )
+            .try_into()
+            .ok()
+            .map(Frame::Headers)
+    }
+}
+
+pub(crate) const FRAME_TYPE_HEADERS: u8 = 0x1;
+const FRAME_TYPE_RST_STREAM: u8 = 0x3;
+pub(crate) const FRAME_TYPE_SETTINGS: u8 = 0x4;
+pub(crate) const FRAME_TYPE_CONTINUATION: u8 = 0x9;
+
+pub(crate) const FLAG_END_STREAM: u8 = 0x1;
+pub(crate) const FLAG_ACK: u8 = 0x1;
+pub(crate) const FLAG_END_HEADERS: u8 = 0x4;
+const FLAG_PADDED: u8 = 0x8;
+const FLAG_PRIORITY: u8 = 0x20;
+
+#[derive(Debug, Clone, Serialize)]
 #[serde(tag = "frame_type", rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum Frame {
+    Data(DataFrame),
     Headers(HeadersFrame),
-    Settings(SettingsFrame),
     Priority(PriorityFrame),
+    RstStream(RstStreamFrame),
+    Settings(SettingsFrame),
+    PushPromise(PushPromiseFrame),
+    Ping(PingFrame),
+    #[serde(rename = "GOAWAY")]
+    GoAway(GoAwayFrame),
     WindowUpdate(WindowUpdateFrame),
-    Unknown(u8),
+    Continuation(ContinuationFrame),
+    Altsvc(AltsvcFrame),
+    Origin(OriginFrame),
+    PriorityUpdate(PriorityUpdateFrame),
+    Unknown(UnknownFrame),
 }
 
+/// HEADERS frames are decoded by `HeaderBlocks` since HPACK decoding is
+/// stateful.
 impl TryFrom<(u8, u8, u32, &[u8])> for Frame {
     type Error = ();
 
     fn try_from((ty, flags, stream_id, payload): (u8, u8, u32, &[u8])) -> Result<Self, ()> {
         match ty {
-            0x1 => (flags, stream_id, payload).try_into().map(Frame::Headers),
+            0x0 => (flags, stream_id, payload).try_into().map(Frame::Data),
             0x2 => (stream_id, payload).try_into().map(Frame::Priority),
+            0x3 => (stream_id, payload).try_into().map(Frame::RstStream),
             0x4 => (stream_id, payload).try_into().map(Frame::Settings),
+            0x5 => (flags, stream_id, payload)
+                .try_into()
+                .map(Frame::PushPromise),
+            0x6 => (flags, stream_id, payload).try_into().map(Frame::Ping),
+            0x7 => (stream_id, payload).try_into().map(Frame::GoAway),
             0x8 => (stream_id, payload).try_into().map(Frame::WindowUpdate),
-            _ => Ok(Frame::Unknown(ty)),
+            0x9 => (flags, stream_id, payload)
+                .try_into()
+                .map(Frame::Continuation),
+            0xa => (stream_id, payload).try_into().map(Frame::Altsvc),
+            0xc => (stream_id, payload).try_into().map(Frame::Origin),
+            0x10 => (stream_id, payload).try_into().map(Frame::PriorityUpdate),
+            _ => Ok(Frame::Unknown(UnknownFrame {
+                ty,
+                flags,
+                stream_id,
+                length: payload.len(),
+            })),
         }
     }
 }
 
-#[derive(Debug, Serialize)]
+/// Strips the padding of a frame with the PADDED flag set.
+fn unpad(flags: u8, payload: &[u8]) -> Result<&[u8], ()> {
+    if flags & FLAG_PADDED == 0 {
+        return Ok(payload);
+    }
+    let (&padding_len, data) = payload.split_first().ok_or(())?;
+    let padding_len = padding_len as usize;
+    if data.len() < padding_len {
+        return Err(());
+    }
+    Ok(&data[..data.len() - padding_len])
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DataFrame {
+    pub stream_id: u32,
+    pub length: usize,
+    pub end_stream: bool,
+}
+
+impl TryFrom<(u8, u32, &[u8])> for DataFrame {
+    type Error = ();
+
+    fn try_from((flags, stream_id, payload): (u8, u32, &[u8])) -> Result<Self, ()> {
+        Ok(DataFrame {
+            stream_id,
+            length: unpad(flags, payload)?.len(),
+            end_stream: flags & FLAG_END_STREAM != 0,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
 pub struct HeadersFrame {
     pub stream_id: u32,
     pub pseudo_headers: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub priority: Option<Priority>,
+    #[serde(skip)]
+    pub headers: Vec<Header>,
+    #[serde(skip)]
+    pub table_size_updates: Vec<u32>,
+    /// Number of frames the header block was split over, i.e. the HEADERS
+    /// frame plus any CONTINUATION frames.
+    #[serde(skip)]
+    pub fragments: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Header {
+    pub name: String,
+    pub value: String,
+    /// `None` when the representations of the header block could not be
+    /// walked.
+    pub encoding: Option<FieldEncoding>,
 }
 
-impl TryFrom<(u8, u32, &[u8])> for HeadersFrame {
+/// Decodes a complete header block, still prefixed with the priority fields
+/// of the HEADERS frame but stripped of its padding.
+impl TryFrom<(u8, u32, &[u8], usize, &mut Decoder<'_>)> for HeadersFrame {
     type Error = ();
 
-    fn try_from((flags, stream_id, payload): (u8, u32, &[u8])) -> Result<Self, ()> {
-        let mut fragment_offset = 0;
-        let padded = flags & 0x8 != 0;
-        if padded {
-            fragment_offset += 1;
+    fn try_from(
+        (flags, stream_id, payload, fragments, decoder): (u8, u32, &[u8], usize, &mut Decoder<'_>),
+    ) -> Result<Self, ()> {
+        let mut block = payload;
+        let mut priority = None;
+        if flags & FLAG_PRIORITY != 0 {
+            priority = Some(block.get(..5).ok_or(())?.try_into()?);
+            block = &block[5..];
         }
-        if flags & 0x20 != 0 {
-            fragment_offset += 5;
-        }
-        if payload.len() < fragment_offset {
-            return Err(());
-        }
-        let padding_len = if padded { payload[0] as usize } else { 0 };
-        let data = &payload[fragment_offset..];
-        if data.len() < padding_len {
-            return Err(());
-        };
-        let mut decoder = Decoder::default();
-        let mut buf = data[..data.len() - padding_len].to_vec();
+        // The block is always decoded so that the dynamic table stays in sync,
+        // even if its representations cannot be told apart.
+        let mut buf = block.to_vec();
         let mut dst = Vec::new();
         if decoder.decode(&mut buf, &mut dst).is_err() {
             return Err(());
         }
+        let encoding =
+            BlockEncoding::scan(block).filter(|encoding| encoding.fields.len() == dst.len());
+        let (table_size_updates, fields) = match encoding {
+            Some(encoding) => (
+                encoding.table_size_updates,
+                encoding.fields.into_iter().map(Some).collect(),
+            ),
+            None => (Vec::new(), vec![None; dst.len()]),
+        };
 
-        let mut pseudo_headers = Vec::new();
-        for (name, _, _) in dst {
-            if let Ok(name) = String::from_utf8(name) {
-                if name.starts_with(':') {
-                    pseudo_headers.push(name);
-                }
-            }
-        }
+        let headers = dst
+            .into_iter()
+            .zip(fields)
+            .map(|((name, value, _), encoding)| Header {
+                name: String::from_utf8_lossy(&name).into_owned(),
+                value: String::from_utf8_lossy(&value).into_owned(),
+                encoding,
+            })
+            .collect::<Vec<_>>();
+        let pseudo_headers = headers
+            .iter()
+            .filter(|header| header.name.starts_with(':'))
+            .map(|header| header.name.clone())
+            .collect();
         Ok(HeadersFrame {
             stream_id,
             pseudo_headers,
+            priority,
+            headers,
+            table_size_updates,
+            fragments,
         })
     }
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 pub struct SettingsFrame {
     pub stream_id: u32,
     pub settings: Vec<Setting>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Setting {
     pub id: u16,
     pub value: u32,
@@ -215,9 +577,12 @@ impl serde::Serialize for Setting {
     where
         S: serde::Serializer,
     {
-        let mut map = serializer.serialize_map(Some(2))?;
-        if (1..=6).contains(&self.id) {
+        let mut map = serializer.serialize_map(None)?;
+        if is_not_grease(&self.id) {
             map.serialize_entry("id", &self.id)?;
+            if let Some(name) = iana::http2_setting(self.id).name {
+                map.serialize_entry("name", name)?;
+            }
             map.serialize_entry("value", &self.value)?;
         } else {
             map.serialize_entry("id", "GREASE")?;
@@ -245,13 +610,13 @@ impl TryFrom<(u32, &[u8])> for SettingsFrame {
     }
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 pub struct PriorityFrame {
     pub stream_id: u32,
     pub priority: Priority,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 pub struct Priority {
     pub dep_stream_id: u32,
     pub weight: u8,
@@ -262,24 +627,32 @@ impl TryFrom<(u32, &[u8])> for PriorityFrame {
     type Error = ();
 
     fn try_from((stream_id, payload): (u32, &[u8])) -> Result<Self, ()> {
+        Ok(PriorityFrame {
+            stream_id,
+            priority: payload.try_into()?,
+        })
+    }
+}
+
+impl TryFrom<&[u8]> for Priority {
+    type Error = ();
+
+    fn try_from(payload: &[u8]) -> Result<Self, ()> {
         if payload.len() != 5 {
             return Err(());
         }
         let exclusive = payload[0] & 0x80 != 0;
         let dep_stream_id =
             u32::from_be_bytes([payload[0] & 0x7f, payload[1], payload[2], payload[3]]);
-        Ok(PriorityFrame {
-            stream_id,
-            priority: Priority {
-                dep_stream_id,
-                weight: payload[4],
-                exclusive,
-            },
+        Ok(Priority {
+            dep_stream_id,
+            weight: payload[4],
+            exclusive,
         })
     }
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 pub struct WindowUpdateFrame {
     pub stream_id: u32,
     pub window_size_increment: u32,
@@ -300,3 +673,253 @@ impl TryFrom<(u32, &[u8])> for WindowUpdateFrame {
         })
     }
 }
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RstStreamFrame {
+    pub stream_id: u32,
+    pub error_code: u32,
+}
+
+impl TryFrom<(u32, &[u8])> for RstStreamFrame {
+    type Error = ();
+
+    fn try_from((stream_id, payload): (u32, &[u8])) -> Result<Self, ()> {
+        let error_code = payload.try_into().map(u32::from_be_bytes).map_err(|_| ())?;
+        Ok(RstStreamFrame {
+            stream_id,
+            error_code,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PushPromiseFrame {
+    pub stream_id: u32,
+    pub promised_stream_id: u32,
+    pub fragment_length: usize,
+    pub end_headers: bool,
+}
+
+impl TryFrom<(u8, u32, &[u8])> for PushPromiseFrame {
+    type Error = ();
+
+    fn try_from((flags, stream_id, payload): (u8, u32, &[u8])) -> Result<Self, ()> {
+        let data = unpad(flags, payload)?;
+        if data.len() < 4 {
+            return Err(());
+        }
+        let promised_stream_id = u32::from_be_bytes([data[0] & 0x7f, data[1], data[2], data[3]]);
+        Ok(PushPromiseFrame {
+            stream_id,
+            promised_stream_id,
+            fragment_length: data.len() - 4,
+            end_headers: flags & FLAG_END_HEADERS != 0,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PingFrame {
+    pub stream_id: u32,
+    pub ack: bool,
+    pub opaque_data: String,
+}
+
+impl TryFrom<(u8, u32, &[u8])> for PingFrame {
+    type Error = ();
+
+    fn try_from((flags, stream_id, payload): (u8, u32, &[u8])) -> Result<Self, ()> {
+        if payload.len() != 8 {
+            return Err(());
+        }
+        Ok(PingFrame {
+            stream_id,
+            ack: flags & FLAG_ACK != 0,
+            opaque_data: hex::encode(payload),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GoAwayFrame {
+    pub stream_id: u32,
+    pub last_stream_id: u32,
+    pub error_code: u32,
+    pub debug_data: String,
+}
+
+impl TryFrom<(u32, &[u8])> for GoAwayFrame {
+    type Error = ();
+
+    fn try_from((stream_id, payload): (u32, &[u8])) -> Result<Self, ()> {
+        if payload.len() < 8 {
+            return Err(());
+        }
+        let last_stream_id =
+            u32::from_be_bytes([payload[0] & 0x7f, payload[1], payload[2], payload[3]]);
+        let error_code = u32::from_be_bytes([payload[4], payload[5], payload[6], payload[7]]);
+        Ok(GoAwayFrame {
+            stream_id,
+            last_stream_id,
+            error_code,
+            debug_data: hex::encode(&payload[8..]),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ContinuationFrame {
+    pub stream_id: u32,
+    pub fragment_length: usize,
+    pub end_headers: bool,
+}
+
+impl TryFrom<(u8, u32, &[u8])> for ContinuationFrame {
+    type Error = ();
+
+    fn try_from((flags, stream_id, payload): (u8, u32, &[u8])) -> Result<Self, ()> {
+        Ok(ContinuationFrame {
+            stream_id,
+            fragment_length: payload.len(),
+            end_headers: flags & FLAG_END_HEADERS != 0,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AltsvcFrame {
+    pub stream_id: u32,
+    pub origin: String,
+    pub field_value: String,
+}
+
+impl TryFrom<(u32, &[u8])> for AltsvcFrame {
+    type Error = ();
+
+    fn try_from((stream_id, payload): (u32, &[u8])) -> Result<Self, ()> {
+        if payload.len() < 2 {
+            return Err(());
+        }
+        let origin_len = u16::from_be_bytes([payload[0], payload[1]]) as usize;
+        let data = &payload[2..];
+        if data.len() < origin_len {
+            return Err(());
+        }
+        let (origin, field_value) = data.split_at(origin_len);
+        Ok(AltsvcFrame {
+            stream_id,
+            origin: String::from_utf8_lossy(origin).into_owned(),
+            field_value: String::from_utf8_lossy(field_value).into_owned(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OriginFrame {
+    pub stream_id: u32,
+    pub origins: Vec<String>,
+}
+
+impl TryFrom<(u32, &[u8])> for OriginFrame {
+    type Error = ();
+
+    fn try_from((stream_id, mut payload): (u32, &[u8])) -> Result<Self, ()> {
+        let mut origins = Vec::new();
+        while !payload.is_empty() {
+            if payload.len() < 2 {
+                return Err(());
+            }
+            let len = u16::from_be_bytes([payload[0], payload[1]]) as usize;
+            let origin = payload.get(2..2 + len).ok_or(())?;
+            origins.push(String::from_utf8_lossy(origin).into_owned());
+            payload = &payload[2 + len..];
+        }
+        Ok(OriginFrame { stream_id, origins })
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PriorityUpdateFrame {
+    pub stream_id: u32,
+    pub prioritized_stream_id: u32,
+    pub priority_field_value: String,
+    pub priority: ExtensiblePriority,
+}
+
+impl TryFrom<(u32, &[u8])> for PriorityUpdateFrame {
+    type Error = ();
+
+    fn try_from((stream_id, payload): (u32, &[u8])) -> Result<Self, ()> {
+        if payload.len() < 4 {
+            return Err(());
+        }
+        let prioritized_stream_id =
+            u32::from_be_bytes([payload[0] & 0x7f, payload[1], payload[2], payload[3]]);
+        let priority_field_value = String::from_utf8_lossy(&payload[4..]).into_owned();
+        Ok(PriorityUpdateFrame {
+            stream_id,
+            prioritized_stream_id,
+            priority: ExtensiblePriority::parse(&priority_field_value),
+            priority_field_value,
+        })
+    }
+}
+
+/// Priority parameters of RFC 9218, as sent in the `priority` header or a
+/// PRIORITY_UPDATE frame. Absent parameters are left as `None` rather than
+/// replaced with their defaults.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ExtensiblePriority {
+    pub urgency: Option<u8>,
+    pub incremental: Option<bool>,
+}
+
+impl ExtensiblePriority {
+    /// Parses a Structured Fields dictionary, ignoring unknown or invalid
+    /// members.
+    pub fn parse(value: &str) -> Self {
+        let mut priority = Self::default();
+        for member in value.split(',') {
+            let member = member.split(';').next().unwrap_or_default().trim();
+            let (key, value) = member.split_once('=').unwrap_or((member, "?1"));
+            match key {
+                "u" => {
+                    if let Ok(urgency @ 0..=7) = value.parse() {
+                        priority.urgency = Some(urgency);
+                    }
+                }
+                "i" => match value {
+                    "?1" => priority.incremental = Some(true),
+                    "?0" => priority.incremental = Some(false),
+                    _ => (),
+                },
+                _ => (),
+            }
+        }
+        priority
+    }
+}
+
+impl std::fmt::Display for ExtensiblePriority {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut params = Vec::new();
+        if let Some(urgency) = self.urgency {
+            params.push(format!("u={urgency}"));
+        }
+        match self.incremental {
+            Some(true) => params.push("i".to_string()),
+            Some(false) => params.push("i=?0".to_string()),
+            None => (),
+        }
+        write!(f, "{}", params.join(","))
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UnknownFrame {
+    #[serde(rename = "type")]
+    pub ty: u8,
+    pub flags: u8,
+    pub stream_id: u32,
+    pub length: usize,
+}
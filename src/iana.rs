@@ -0,0 +1,388 @@
+use crate::ja3::is_not_grease;
+use serde_derive::Serialize;
+use std::fmt;
+
+#[derive(Clone, Copy, Serialize)]
+pub struct Named<T> {
+    pub value: T,
+    pub name: Option<&'static str>,
+}
+
+impl<T: fmt::Display> fmt::Display for Named<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.name {
+            Some(name) => write!(f, "{name} ({})", self.value),
+            None => write!(f, "Unknown ({})", self.value),
+        }
+    }
+}
+
+pub fn version(value: u16) -> Named<u16> {
+    lookup(VERSIONS, value)
+}
+
+pub fn cipher_suite(value: u16) -> Named<u16> {
+    lookup(CIPHER_SUITES, value)
+}
+
+pub fn extension_type(value: u16) -> Named<u16> {
+    lookup(EXTENSION_TYPES, value)
+}
+
+pub fn named_group(value: u16) -> Named<u16> {
+    lookup(NAMED_GROUPS, value)
+}
+
+pub fn signature_scheme(value: u16) -> Named<u16> {
+    lookup(SIGNATURE_SCHEMES, value)
+}
+
+pub fn ec_point_format(value: u8) -> Named<u8> {
+    Named {
+        value,
+        name: find(EC_POINT_FORMATS, value),
+    }
+}
+
+pub fn compression_method(value: u8) -> Named<u8> {
+    Named {
+        value,
+        name: find(COMPRESSION_METHODS, value),
+    }
+}
+
+pub fn psk_key_exchange_mode(value: u8) -> Named<u8> {
+    Named {
+        value,
+        name: find(PSK_KEY_EXCHANGE_MODES, value),
+    }
+}
+
+pub fn http2_setting(value: u16) -> Named<u16> {
+    lookup(HTTP2_SETTINGS, value)
+}
+
+fn lookup(table: &[(u16, &'static str)], value: u16) -> Named<u16> {
+    let name = if is_not_grease(&value) {
+        find(table, value)
+    } else {
+        Some("GREASE")
+    };
+    Named { value, name }
+}
+
+fn find<T: PartialEq>(table: &[(T, &'static str)], value: T) -> Option<&'static str> {
+    table
+        .iter()
+        .find(|(v, _)| *v == value)
+        .map(|(_, name)| *name)
+}
+
+const VERSIONS: &[(u16, &str)] = &[
+    (0x0002, "SSL 2.0"),
+    (0x0300, "SSL 3.0"),
+    (0x0301, "TLS 1.0"),
+    (0x0302, "TLS 1.1"),
+    (0x0303, "TLS 1.2"),
+    (0x0304, "TLS 1.3"),
+    (0xfeff, "DTLS 1.0"),
+    (0xfefd, "DTLS 1.2"),
+    (0xfefc, "DTLS 1.3"),
+];
+
+const CIPHER_SUITES: &[(u16, &str)] = &[
+    (0x0000, "TLS_NULL_WITH_NULL_NULL"),
+    (0x0001, "TLS_RSA_WITH_NULL_MD5"),
+    (0x0002, "TLS_RSA_WITH_NULL_SHA"),
+    (0x0003, "TLS_RSA_EXPORT_WITH_RC4_40_MD5"),
+    (0x0004, "TLS_RSA_WITH_RC4_128_MD5"),
+    (0x0005, "TLS_RSA_WITH_RC4_128_SHA"),
+    (0x0006, "TLS_RSA_EXPORT_WITH_RC2_CBC_40_MD5"),
+    (0x0007, "TLS_RSA_WITH_IDEA_CBC_SHA"),
+    (0x0008, "TLS_RSA_EXPORT_WITH_DES40_CBC_SHA"),
+    (0x0009, "TLS_RSA_WITH_DES_CBC_SHA"),
+    (0x000a, "TLS_RSA_WITH_3DES_EDE_CBC_SHA"),
+    (0x000d, "TLS_DH_DSS_WITH_3DES_EDE_CBC_SHA"),
+    (0x0010, "TLS_DH_RSA_WITH_3DES_EDE_CBC_SHA"),
+    (0x0013, "TLS_DHE_DSS_WITH_3DES_EDE_CBC_SHA"),
+    (0x0016, "TLS_DHE_RSA_WITH_3DES_EDE_CBC_SHA"),
+    (0x002f, "TLS_RSA_WITH_AES_128_CBC_SHA"),
+    (0x0030, "TLS_DH_DSS_WITH_AES_128_CBC_SHA"),
+    (0x0031, "TLS_DH_RSA_WITH_AES_128_CBC_SHA"),
+    (0x0032, "TLS_DHE_DSS_WITH_AES_128_CBC_SHA"),
+    (0x0033, "TLS_DHE_RSA_WITH_AES_128_CBC_SHA"),
+    (0x0034, "TLS_DH_anon_WITH_AES_128_CBC_SHA"),
+    (0x0035, "TLS_RSA_WITH_AES_256_CBC_SHA"),
+    (0x0036, "TLS_DH_DSS_WITH_AES_256_CBC_SHA"),
+    (0x0037, "TLS_DH_RSA_WITH_AES_256_CBC_SHA"),
+    (0x0038, "TLS_DHE_DSS_WITH_AES_256_CBC_SHA"),
+    (0x0039, "TLS_DHE_RSA_WITH_AES_256_CBC_SHA"),
+    (0x003a, "TLS_DH_anon_WITH_AES_256_CBC_SHA"),
+    (0x003b, "TLS_RSA_WITH_NULL_SHA256"),
+    (0x003c, "TLS_RSA_WITH_AES_128_CBC_SHA256"),
+    (0x003d, "TLS_RSA_WITH_AES_256_CBC_SHA256"),
+    (0x0040, "TLS_DHE_DSS_WITH_AES_128_CBC_SHA256"),
+    (0x0041, "TLS_RSA_WITH_CAMELLIA_128_CBC_SHA"),
+    (0x0045, "TLS_DHE_RSA_WITH_CAMELLIA_128_CBC_SHA"),
+    (0x0067, "TLS_DHE_RSA_WITH_AES_128_CBC_SHA256"),
+    (0x006a, "TLS_DHE_DSS_WITH_AES_256_CBC_SHA256"),
+    (0x006b, "TLS_DHE_RSA_WITH_AES_256_CBC_SHA256"),
+    (0x0084, "TLS_RSA_WITH_CAMELLIA_256_CBC_SHA"),
+    (0x0088, "TLS_DHE_RSA_WITH_CAMELLIA_256_CBC_SHA"),
+    (0x008c, "TLS_PSK_WITH_AES_128_CBC_SHA"),
+    (0x008d, "TLS_PSK_WITH_AES_256_CBC_SHA"),
+    (0x009c, "TLS_RSA_WITH_AES_128_GCM_SHA256"),
+    (0x009d, "TLS_RSA_WITH_AES_256_GCM_SHA384"),
+    (0x009e, "TLS_DHE_RSA_WITH_AES_128_GCM_SHA256"),
+    (0x009f, "TLS_DHE_RSA_WITH_AES_256_GCM_SHA384"),
+    (0x00a2, "TLS_DHE_DSS_WITH_AES_128_GCM_SHA256"),
+    (0x00a3, "TLS_DHE_DSS_WITH_AES_256_GCM_SHA384"),
+    (0x00a8, "TLS_PSK_WITH_AES_128_GCM_SHA256"),
+    (0x00a9, "TLS_PSK_WITH_AES_256_GCM_SHA384"),
+    (0x00ff, "TLS_EMPTY_RENEGOTIATION_INFO_SCSV"),
+    (0x1301, "TLS_AES_128_GCM_SHA256"),
+    (0x1302, "TLS_AES_256_GCM_SHA384"),
+    (0x1303, "TLS_CHACHA20_POLY1305_SHA256"),
+    (0x1304, "TLS_AES_128_CCM_SHA256"),
+    (0x1305, "TLS_AES_128_CCM_8_SHA256"),
+    (0x5600, "TLS_FALLBACK_SCSV"),
+    (0xc001, "TLS_ECDH_ECDSA_WITH_NULL_SHA"),
+    (0xc002, "TLS_ECDH_ECDSA_WITH_RC4_128_SHA"),
+    (0xc003, "TLS_ECDH_ECDSA_WITH_3DES_EDE_CBC_SHA"),
+    (0xc004, "TLS_ECDH_ECDSA_WITH_AES_128_CBC_SHA"),
+    (0xc005, "TLS_ECDH_ECDSA_WITH_AES_256_CBC_SHA"),
+    (0xc006, "TLS_ECDHE_ECDSA_WITH_NULL_SHA"),
+    (0xc007, "TLS_ECDHE_ECDSA_WITH_RC4_128_SHA"),
+    (0xc008, "TLS_ECDHE_ECDSA_WITH_3DES_EDE_CBC_SHA"),
+    (0xc009, "TLS_ECDHE_ECDSA_WITH_AES_128_CBC_SHA"),
+    (0xc00a, "TLS_ECDHE_ECDSA_WITH_AES_256_CBC_SHA"),
+    (0xc00b, "TLS_ECDH_RSA_WITH_NULL_SHA"),
+    (0xc00c, "TLS_ECDH_RSA_WITH_RC4_128_SHA"),
+    (0xc00d, "TLS_ECDH_RSA_WITH_3DES_EDE_CBC_SHA"),
+    (0xc00e, "TLS_ECDH_RSA_WITH_AES_128_CBC_SHA"),
+    (0xc00f, "TLS_ECDH_RSA_WITH_AES_256_CBC_SHA"),
+    (0xc010, "TLS_ECDHE_RSA_WITH_NULL_SHA"),
+    (0xc011, "TLS_ECDHE_RSA_WITH_RC4_128_SHA"),
+    (0xc012, "TLS_ECDHE_RSA_WITH_3DES_EDE_CBC_SHA"),
+    (0xc013, "TLS_ECDHE_RSA_WITH_AES_128_CBC_SHA"),
+    (0xc014, "TLS_ECDHE_RSA_WITH_AES_256_CBC_SHA"),
+    (0xc023, "TLS_ECDHE_ECDSA_WITH_AES_128_CBC_SHA256"),
+    (0xc024, "TLS_ECDHE_ECDSA_WITH_AES_256_CBC_SHA384"),
+    (0xc025, "TLS_ECDH_ECDSA_WITH_AES_128_CBC_SHA256"),
+    (0xc026, "TLS_ECDH_ECDSA_WITH_AES_256_CBC_SHA384"),
+    (0xc027, "TLS_ECDHE_RSA_WITH_AES_128_CBC_SHA256"),
+    (0xc028, "TLS_ECDHE_RSA_WITH_AES_256_CBC_SHA384"),
+    (0xc029, "TLS_ECDH_RSA_WITH_AES_128_CBC_SHA256"),
+    (0xc02a, "TLS_ECDH_RSA_WITH_AES_256_CBC_SHA384"),
+    (0xc02b, "TLS_ECDHE_ECDSA_WITH_AES_128_GCM_SHA256"),
+    (0xc02c, "TLS_ECDHE_ECDSA_WITH_AES_256_GCM_SHA384"),
+    (0xc02d, "TLS_ECDH_ECDSA_WITH_AES_128_GCM_SHA256"),
+    (0xc02e, "TLS_ECDH_ECDSA_WITH_AES_256_GCM_SHA384"),
+    (0xc02f, "TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256"),
+    (0xc030, "TLS_ECDHE_RSA_WITH_AES_256_GCM_SHA384"),
+    (0xc031, "TLS_ECDH_RSA_WITH_AES_128_GCM_SHA256"),
+    (0xc032, "TLS_ECDH_RSA_WITH_AES_256_GCM_SHA384"),
+    (0xc035, "TLS_ECDHE_PSK_WITH_AES_128_CBC_SHA"),
+    (0xc036, "TLS_ECDHE_PSK_WITH_AES_256_CBC_SHA"),
+    (0xc072, "TLS_ECDHE_ECDSA_WITH_CAMELLIA_128_CBC_SHA256"),
+    (0xc073, "TLS_ECDHE_ECDSA_WITH_CAMELLIA_256_CBC_SHA384"),
+    (0xc076, "TLS_ECDHE_RSA_WITH_CAMELLIA_128_CBC_SHA256"),
+    (0xc077, "TLS_ECDHE_RSA_WITH_CAMELLIA_256_CBC_SHA384"),
+    (0xc09c, "TLS_RSA_WITH_AES_128_CCM"),
+    (0xc09d, "TLS_RSA_WITH_AES_256_CCM"),
+    (0xc09e, "TLS_DHE_RSA_WITH_AES_128_CCM"),
+    (0xc09f, "TLS_DHE_RSA_WITH_AES_256_CCM"),
+    (0xc0a0, "TLS_RSA_WITH_AES_128_CCM_8"),
+    (0xc0a1, "TLS_RSA_WITH_AES_256_CCM_8"),
+    (0xc0a2, "TLS_DHE_RSA_WITH_AES_128_CCM_8"),
+    (0xc0a3, "TLS_DHE_RSA_WITH_AES_256_CCM_8"),
+    (0xc0ac, "TLS_ECDHE_ECDSA_WITH_AES_128_CCM"),
+    (0xc0ad, "TLS_ECDHE_ECDSA_WITH_AES_256_CCM"),
+    (0xc0ae, "TLS_ECDHE_ECDSA_WITH_AES_128_CCM_8"),
+    (0xc0af, "TLS_ECDHE_ECDSA_WITH_AES_256_CCM_8"),
+    (0xcca8, "TLS_ECDHE_RSA_WITH_CHACHA20_POLY1305_SHA256"),
+    (0xcca9, "TLS_ECDHE_ECDSA_WITH_CHACHA20_POLY1305_SHA256"),
+    (0xccaa, "TLS_DHE_RSA_WITH_CHACHA20_POLY1305_SHA256"),
+    (0xccab, "TLS_PSK_WITH_CHACHA20_POLY1305_SHA256"),
+    (0xccac, "TLS_ECDHE_PSK_WITH_CHACHA20_POLY1305_SHA256"),
+    (0xccad, "TLS_DHE_PSK_WITH_CHACHA20_POLY1305_SHA256"),
+    (0xccae, "TLS_RSA_PSK_WITH_CHACHA20_POLY1305_SHA256"),
+    (0xd001, "TLS_ECDHE_PSK_WITH_AES_128_GCM_SHA256"),
+    (0xd002, "TLS_ECDHE_PSK_WITH_AES_256_GCM_SHA384"),
+];
+
+const EXTENSION_TYPES: &[(u16, &str)] = &[
+    (0x0000, "server_name"),
+    (0x0001, "max_fragment_length"),
+    (0x0002, "client_certificate_url"),
+    (0x0003, "trusted_ca_keys"),
+    (0x0004, "truncated_hmac"),
+    (0x0005, "status_request"),
+    (0x0006, "user_mapping"),
+    (0x0007, "client_authz"),
+    (0x0008, "server_authz"),
+    (0x0009, "cert_type"),
+    (0x000a, "supported_groups"),
+    (0x000b, "ec_point_formats"),
+    (0x000c, "srp"),
+    (0x000d, "signature_algorithms"),
+    (0x000e, "use_srtp"),
+    (0x000f, "heartbeat"),
+    (0x0010, "application_layer_protocol_negotiation"),
+    (0x0011, "status_request_v2"),
+    (0x0012, "signed_certificate_timestamp"),
+    (0x0013, "client_certificate_type"),
+    (0x0014, "server_certificate_type"),
+    (0x0015, "padding"),
+    (0x0016, "encrypt_then_mac"),
+    (0x0017, "extended_master_secret"),
+    (0x0018, "token_binding"),
+    (0x0019, "cached_info"),
+    (0x001a, "tls_lts"),
+    (0x001b, "compress_certificate"),
+    (0x001c, "record_size_limit"),
+    (0x001d, "pwd_protect"),
+    (0x001e, "pwd_clear"),
+    (0x001f, "password_salt"),
+    (0x0020, "ticket_pinning"),
+    (0x0021, "tls_cert_with_extern_psk"),
+    (0x0022, "delegated_credential"),
+    (0x0023, "session_ticket"),
+    (0x0024, "TLMSP"),
+    (0x0025, "TLMSP_proxying"),
+    (0x0026, "TLMSP_delegate"),
+    (0x0027, "supported_ekt_ciphers"),
+    (0x0029, "pre_shared_key"),
+    (0x002a, "early_data"),
+    (0x002b, "supported_versions"),
+    (0x002c, "cookie"),
+    (0x002d, "psk_key_exchange_modes"),
+    (0x002f, "certificate_authorities"),
+    (0x0030, "oid_filters"),
+    (0x0031, "post_handshake_auth"),
+    (0x0032, "signature_algorithms_cert"),
+    (0x0033, "key_share"),
+    (0x0034, "transparency_info"),
+    (0x0036, "connection_id"),
+    (0x0037, "external_id_hash"),
+    (0x0038, "external_session_id"),
+    (0x0039, "quic_transport_parameters"),
+    (0x003a, "ticket_request"),
+    (0x003b, "dnssec_chain"),
+    (0x003c, "sequence_number_encryption_algorithms"),
+    (0x003d, "rrc"),
+    (0x3374, "next_protocol_negotiation"),
+    (0x4469, "application_settings_old"),
+    (0x44cd, "application_settings"),
+    (0xfe0d, "encrypted_client_hello"),
+    (0xff01, "renegotiation_info"),
+];
+
+const NAMED_GROUPS: &[(u16, &str)] = &[
+    (0x0001, "sect163k1"),
+    (0x0002, "sect163r1"),
+    (0x0003, "sect163r2"),
+    (0x0004, "sect193r1"),
+    (0x0005, "sect193r2"),
+    (0x0006, "sect233k1"),
+    (0x0007, "sect233r1"),
+    (0x0008, "sect239k1"),
+    (0x0009, "sect283k1"),
+    (0x000a, "sect283r1"),
+    (0x000b, "sect409k1"),
+    (0x000c, "sect409r1"),
+    (0x000d, "sect571k1"),
+    (0x000e, "sect571r1"),
+    (0x000f, "secp160k1"),
+    (0x0010, "secp160r1"),
+    (0x0011, "secp160r2"),
+    (0x0012, "secp192k1"),
+    (0x0013, "secp192r1"),
+    (0x0014, "secp224k1"),
+    (0x0015, "secp224r1"),
+    (0x0016, "secp256k1"),
+    (0x0017, "secp256r1"),
+    (0x0018, "secp384r1"),
+    (0x0019, "secp521r1"),
+    (0x001a, "brainpoolP256r1"),
+    (0x001b, "brainpoolP384r1"),
+    (0x001c, "brainpoolP512r1"),
+    (0x001d, "x25519"),
+    (0x001e, "x448"),
+    (0x001f, "brainpoolP256r1tls13"),
+    (0x0020, "brainpoolP384r1tls13"),
+    (0x0021, "brainpoolP512r1tls13"),
+    (0x0100, "ffdhe2048"),
+    (0x0101, "ffdhe3072"),
+    (0x0102, "ffdhe4096"),
+    (0x0103, "ffdhe6144"),
+    (0x0104, "ffdhe8192"),
+    (0x0200, "MLKEM512"),
+    (0x0201, "MLKEM768"),
+    (0x0202, "MLKEM1024"),
+    (0x11eb, "SecP256r1MLKEM768"),
+    (0x11ec, "X25519MLKEM768"),
+    (0x11ed, "SecP384r1MLKEM1024"),
+    (0x6399, "X25519Kyber768Draft00"),
+    (0x639a, "SecP256r1Kyber768Draft00"),
+    (0xff01, "arbitrary_explicit_prime_curves"),
+    (0xff02, "arbitrary_explicit_char2_curves"),
+];
+
+const SIGNATURE_SCHEMES: &[(u16, &str)] = &[
+    (0x0201, "rsa_pkcs1_sha1"),
+    (0x0202, "dsa_sha1"),
+    (0x0203, "ecdsa_sha1"),
+    (0x0301, "rsa_pkcs1_sha224"),
+    (0x0302, "dsa_sha224"),
+    (0x0303, "ecdsa_sha224"),
+    (0x0401, "rsa_pkcs1_sha256"),
+    (0x0402, "dsa_sha256"),
+    (0x0403, "ecdsa_secp256r1_sha256"),
+    (0x0420, "rsa_pkcs1_sha256_legacy"),
+    (0x0501, "rsa_pkcs1_sha384"),
+    (0x0502, "dsa_sha384"),
+    (0x0503, "ecdsa_secp384r1_sha384"),
+    (0x0520, "rsa_pkcs1_sha384_legacy"),
+    (0x0601, "rsa_pkcs1_sha512"),
+    (0x0602, "dsa_sha512"),
+    (0x0603, "ecdsa_secp521r1_sha512"),
+    (0x0620, "rsa_pkcs1_sha512_legacy"),
+    (0x0704, "eccsi_sha256"),
+    (0x0804, "rsa_pss_rsae_sha256"),
+    (0x0805, "rsa_pss_rsae_sha384"),
+    (0x0806, "rsa_pss_rsae_sha512"),
+    (0x0807, "ed25519"),
+    (0x0808, "ed448"),
+    (0x0809, "rsa_pss_pss_sha256"),
+    (0x080a, "rsa_pss_pss_sha384"),
+    (0x080b, "rsa_pss_pss_sha512"),
+    (0x081a, "ecdsa_brainpoolP256r1tls13_sha256"),
+    (0x081b, "ecdsa_brainpoolP384r1tls13_sha384"),
+    (0x081c, "ecdsa_brainpoolP512r1tls13_sha512"),
+    (0x0904, "mldsa44"),
+    (0x0905, "mldsa65"),
+    (0x0906, "mldsa87"),
+];
+
+const EC_POINT_FORMATS: &[(u8, &str)] = &[
+    (0, "uncompressed"),
+    (1, "ansiX962_compressed_prime"),
+    (2, "ansiX962_compressed_char2"),
+];
+
+const COMPRESSION_METHODS: &[(u8, &str)] = &[(0, "null"), (1, "DEFLATE"), (64, "LZS")];
+
+const PSK_KEY_EXCHANGE_MODES: &[(u8, &str)] = &[(0, "psk_ke"), (1, "psk_dhe_ke")];
+
+const HTTP2_SETTINGS: &[(u16, &str)] = &[
+    (0x1, "HEADER_TABLE_SIZE"),
+    (0x2, "ENABLE_PUSH"),
+    (0x3, "MAX_CONCURRENT_STREAMS"),
+    (0x4, "INITIAL_WINDOW_SIZE"),
+    (0x5, "MAX_FRAME_SIZE"),
+    (0x6, "MAX_HEADER_LIST_SIZE"),
+    (0x8, "ENABLE_CONNECT_PROTOCOL"),
+    (0x9, "NO_RFC7540_PRIORITIES"),
+    (0x10, "TLS_RENEG_PERMITTED"),
+];
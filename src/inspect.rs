@@ -0,0 +1,13 @@
+/// A stream wrapper that passes data through unchanged while capturing what
+/// the peer sends, so that inspectors can be layered over each other and over
+/// any `AsyncRead + AsyncWrite` stream.
+pub trait Inspect {
+    type Inner;
+    type Capture;
+
+    /// Returns the wrapped stream.
+    fn get_ref(&self) -> &Self::Inner;
+
+    /// Returns what has been captured so far.
+    fn capture(&self) -> &Self::Capture;
+}
@@ -1,4 +1,4 @@
-use rustls::internal::msgs::handshake::{ClientExtension, ClientHelloPayload};
+use crate::handshake::{ClientHello, ExtensionPayload};
 use serde_derive::Serialize;
 
 #[derive(Clone, Serialize)]
@@ -8,21 +8,18 @@ pub struct Ja3 {
 }
 
 impl Ja3 {
-    pub fn new(hello: &ClientHelloPayload, sort_ext: bool) -> Self {
-        let version = hello.client_version.get_u16();
+    pub fn new(hello: &ClientHello, sort_ext: bool) -> Self {
+        let version = hello.legacy_version;
         let ciphers = hello
-            .cipher_suites
-            .iter()
-            .map(|cipher| cipher.get_u16())
+            .cipher_suites()
             .filter(is_not_grease)
             .map(|n| n.to_string())
             .collect::<Vec<_>>();
         let ciphers = ciphers.join("-");
 
         let mut extensions = hello
-            .extensions
-            .iter()
-            .map(|ext| ext.get_type().get_u16())
+            .extensions()
+            .map(|ext| ext.ty)
             .filter(is_not_grease)
             .map(|n| n.to_string())
             .collect::<Vec<_>>();
@@ -34,28 +31,24 @@ impl Ja3 {
         let extensions = extensions.join("-");
 
         let curves = hello
-            .extensions
-            .iter()
-            .filter_map(|ext| match ext {
-                ClientExtension::NamedGroups(curves) => Some(curves),
+            .extensions()
+            .filter_map(|ext| match ext.payload() {
+                ExtensionPayload::SupportedGroups(curves) => Some(curves),
                 _ => None,
             })
             .flatten()
-            .map(|curve| curve.get_u16())
             .filter(is_not_grease)
             .map(|n| n.to_string())
             .collect::<Vec<_>>();
         let curves = curves.join("-");
 
         let points = hello
-            .extensions
-            .iter()
-            .filter_map(|ext| match ext {
-                ClientExtension::ECPointFormats(points) => Some(points),
+            .extensions()
+            .filter_map(|ext| match ext.payload() {
+                ExtensionPayload::EcPointFormats(points) => Some(points),
                 _ => None,
             })
             .flatten()
-            .map(|points| points.get_u8())
             .map(|n| n.to_string())
             .collect::<Vec<_>>();
         let points = points.join("-");
@@ -70,6 +63,6 @@ impl Ja3 {
     }
 }
 
-fn is_not_grease(v: &u16) -> bool {
+pub fn is_not_grease(v: &u16) -> bool {
     *v & 0x0f0f != 0x0a0a
 }
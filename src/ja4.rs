@@ -0,0 +1,142 @@
+use crate::handshake::{ClientHello, ExtensionPayload, EXT_ALPN, EXT_SERVER_NAME};
+use crate::ja3::is_not_grease;
+use serde_derive::Serialize;
+use sha2::{Digest, Sha256};
+
+#[derive(Clone, Serialize)]
+pub struct Ja4 {
+    pub text: String,
+    pub raw: String,
+}
+
+impl Ja4 {
+    pub fn new(hello: &ClientHello, original_order: bool) -> Self {
+        let ciphers = hello
+            .cipher_suites()
+            .filter(is_not_grease)
+            .collect::<Vec<_>>();
+
+        let extensions = hello
+            .extensions()
+            .map(|ext| ext.ty)
+            .filter(is_not_grease)
+            .collect::<Vec<_>>();
+
+        let version = hello
+            .extensions()
+            .filter_map(|ext| match ext.payload() {
+                ExtensionPayload::SupportedVersions(versions) => Some(versions),
+                _ => None,
+            })
+            .flatten()
+            .filter(is_not_grease)
+            .max()
+            .unwrap_or(hello.legacy_version);
+
+        let sni = if extensions.contains(&EXT_SERVER_NAME) {
+            'd'
+        } else {
+            'i'
+        };
+
+        let alpn = hello
+            .extensions()
+            .find_map(|ext| match ext.payload() {
+                ExtensionPayload::Alpn(protocols) => protocols.first().copied(),
+                _ => None,
+            })
+            .map(alpn_chars)
+            .unwrap_or_else(|| "00".to_string());
+
+        let prefix = format!(
+            "t{}{sni}{:02}{:02}{alpn}",
+            version_str(version),
+            ciphers.len().min(99),
+            extensions.len().min(99),
+        );
+
+        let mut ciphers = ciphers;
+        let mut extensions = extensions;
+        if !original_order {
+            ciphers.sort_unstable();
+            extensions.retain(|ext| *ext != EXT_SERVER_NAME && *ext != EXT_ALPN);
+            extensions.sort_unstable();
+        }
+
+        let sig_algs = hello
+            .extensions()
+            .filter_map(|ext| match ext.payload() {
+                ExtensionPayload::SignatureAlgorithms(schemes) => Some(schemes),
+                _ => None,
+            })
+            .flatten()
+            .filter(is_not_grease)
+            .collect::<Vec<_>>();
+
+        let ciphers = hex_list(&ciphers);
+        let mut extensions = hex_list(&extensions);
+        if !sig_algs.is_empty() {
+            extensions = format!("{extensions}_{}", hex_list(&sig_algs));
+        }
+
+        let text = format!(
+            "{prefix}_{}_{}",
+            truncated_hash(&ciphers),
+            truncated_hash(&extensions)
+        );
+        let raw = format!("{prefix}_{ciphers}_{extensions}");
+
+        Self { text, raw }
+    }
+}
+
+fn version_str(version: u16) -> &'static str {
+    match version {
+        0x0304 => "13",
+        0x0303 => "12",
+        0x0302 => "11",
+        0x0301 => "10",
+        0x0300 => "s3",
+        0x0002 => "s2",
+        0xfeff => "d1",
+        0xfefd => "d2",
+        0xfefc => "d3",
+        _ => "00",
+    }
+}
+
+fn alpn_chars(protocol: &[u8]) -> String {
+    match (protocol.first(), protocol.last()) {
+        (Some(first), Some(last))
+            if first.is_ascii_alphanumeric() && last.is_ascii_alphanumeric() =>
+        {
+            format!("{}{}", *first as char, *last as char)
+        }
+        (Some(_), Some(_)) => {
+            let hex = hex::encode(protocol);
+            let mut chars = hex.chars();
+            format!(
+                "{}{}",
+                chars.next().unwrap_or('0'),
+                chars.last().unwrap_or('0')
+            )
+        }
+        _ => "00".to_string(),
+    }
+}
+
+fn hex_list(values: &[u16]) -> String {
+    values
+        .iter()
+        .map(|n| format!("{n:04x}"))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn truncated_hash(text: &str) -> String {
+    if text.is_empty() {
+        return "000000000000".to_string();
+    }
+    let hash = hex::encode(Sha256::digest(text.as_bytes()));
+    hash[..12].to_string()
+}
@@ -0,0 +1,32 @@
+//! Stream inspectors that capture the TLS handshake, HTTP/2 frames and HTTP/1
+//! request heads sent by a client, along with the fingerprints computed from
+//! them. The inspectors wrap any `AsyncRead + AsyncWrite` stream, so they can be
+//! layered in front of other hyper or tokio servers as well.
+
+pub mod akamai;
+pub mod client_hello;
+pub mod connection;
+pub mod h2c;
+pub mod handler;
+pub mod handshake;
+pub mod hpack;
+pub mod http1;
+pub mod http2;
+pub mod iana;
+pub mod inspect;
+pub mod ja3;
+pub mod ja4;
+pub mod listener;
+pub mod negotiated;
+pub mod priorities;
+pub mod priority_tree;
+pub mod proxy;
+pub mod report;
+pub mod tcp;
+pub mod tls;
+pub mod ts1;
+
+pub use http1::Http1Inspector;
+pub use http2::Http2Inspector;
+pub use inspect::Inspect;
+pub use tls::TlsInspctor;
@@ -0,0 +1,111 @@
+use socket2::{Domain, Protocol, Socket, Type};
+use std::io;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::str::FromStr;
+use tokio::net::TcpListener;
+
+const DEFAULT_ALPN: &[&str] = &["h2", "http/1.1"];
+const LISTEN_BACKLOG: i32 = 1024;
+
+/// A TLS listener given on the command line as `ADDR[,OPTION...]`, where the
+/// options override the global TLS settings:
+///
+/// - `certs=PATH` and `key=PATH`
+/// - `alpn=PROTOCOL[:PROTOCOL...]`, or `alpn=` to disable ALPN
+/// - `force-hrr`
+#[derive(Debug, Clone)]
+pub struct ListenerSpec {
+    pub addr: SocketAddr,
+    pub certs: Option<PathBuf>,
+    pub key: Option<PathBuf>,
+    pub alpn: Vec<String>,
+    pub force_hrr: bool,
+}
+
+impl FromStr for ListenerSpec {
+    type Err = String;
+
+    fn from_str(spec: &str) -> Result<Self, String> {
+        let mut options = spec.split(',');
+        let addr = options.next().unwrap_or_default();
+        let mut listener = Self {
+            addr: addr
+                .parse()
+                .map_err(|err| format!("invalid socket address `{addr}`: {err}"))?,
+            certs: None,
+            key: None,
+            alpn: DEFAULT_ALPN
+                .iter()
+                .map(|protocol| protocol.to_string())
+                .collect(),
+            force_hrr: false,
+        };
+        for option in options {
+            match option.split_once('=') {
+                Some(("certs", path)) => listener.certs = Some(path.into()),
+                Some(("key", path)) => listener.key = Some(path.into()),
+                Some(("alpn", protocols)) => {
+                    listener.alpn = protocols
+                        .split(':')
+                        .filter(|protocol| !protocol.is_empty())
+                        .map(str::to_string)
+                        .collect();
+                }
+                None if option == "force-hrr" => listener.force_hrr = true,
+                _ => return Err(format!("unknown listener option `{option}`")),
+            }
+        }
+        Ok(listener)
+    }
+}
+
+/// Binds `workers` sockets to `addr`. With more than one worker, the sockets
+/// share the address through SO_REUSEPORT and the kernel spreads incoming
+/// connections across them.
+///
+/// A socket bound to the IPv6 unspecified address also accepts IPv4
+/// connections, unless `v6_only` is set because the IPv4 unspecified address
+/// is bound separately on the same port.
+pub fn bind(addr: SocketAddr, workers: usize, v6_only: bool) -> io::Result<Vec<TcpListener>> {
+    (0..workers.max(1))
+        .map(|_| {
+            let socket = Socket::new(Domain::for_address(addr), Type::STREAM, Some(Protocol::TCP))?;
+            if addr.is_ipv6() {
+                socket.set_only_v6(v6_only || !addr.ip().is_unspecified())?;
+            }
+            #[cfg(not(windows))]
+            socket.set_reuse_address(true)?;
+            if workers > 1 {
+                reuse_port(&socket)?;
+            }
+            socket.set_nonblocking(true)?;
+            socket.bind(&addr.into())?;
+            socket.listen(LISTEN_BACKLOG)?;
+            TcpListener::from_std(socket.into())
+        })
+        .collect()
+}
+
+#[cfg(all(unix, not(any(target_os = "solaris", target_os = "illumos"))))]
+fn reuse_port(socket: &Socket) -> io::Result<()> {
+    socket.set_reuse_port(true)
+}
+
+#[cfg(not(all(unix, not(any(target_os = "solaris", target_os = "illumos")))))]
+fn reuse_port(_socket: &Socket) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "SO_REUSEPORT is not available on this platform",
+    ))
+}
+
+/// Whether `addr` must be bound as IPv6-only so that it does not collide with
+/// an IPv4 wildcard listener on the same port.
+pub fn needs_v6_only(addr: SocketAddr, addrs: &[SocketAddr]) -> bool {
+    addr.is_ipv6()
+        && addr.ip().is_unspecified()
+        && addrs.iter().any(|other| {
+            other.is_ipv4() && other.ip().is_unspecified() && other.port() == addr.port()
+        })
+}
@@ -1,93 +1,224 @@
 use anyhow::Result;
 use clap::Parser;
-use http::Request;
-use hyper::{server::conn::Http, service::service_fn, Body};
+use http::{Request, Version};
+use hyper::{server::conn::Http, service::service_fn, upgrade::OnUpgrade, Body};
 use std::fs::File;
 use std::io::{self, BufReader};
 use std::net::SocketAddr;
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncRead, AsyncWrite};
 use tokio::net::TcpListener;
+use tokio::task::JoinSet;
 use tokio_rustls::rustls::{self, Certificate, PrivateKey};
 use tokio_rustls::TlsAcceptor;
 
-mod akamai;
-mod handler;
-mod http2;
-mod ts1;
-mod ja3;
-mod report;
-mod tls;
-
-use handler::*;
-use http2::*;
-use report::*;
-use tls::*;
+use pawprint::handshake::{ClientHello, ExtensionPayload};
+use pawprint::{
+    connection::*, h2c::*, handler::*, http1::*, http2::*, inspect::*, listener::*, negotiated::*,
+    proxy::*, report::*, tcp::*, tls::*,
+};
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 struct Args {
-    /// Socket address
-    addr: SocketAddr,
+    /// Socket addresses to serve TLS on, each optionally followed by
+    /// listener-specific settings:
+    /// ADDR[,certs=PATH][,key=PATH][,alpn=PROTOCOL[:PROTOCOL...]][,force-hrr]
+    #[arg(required = true)]
+    listeners: Vec<ListenerSpec>,
+
+    /// Certificate chain file, for listeners without their own
+    #[arg(long)]
+    certs: Option<PathBuf>,
 
-    /// Certificate chain file
+    /// Private key file, for listeners without their own
     #[arg(long)]
-    certs: PathBuf,
+    key: Option<PathBuf>,
 
-    /// Private key file
+    /// Only offer a key exchange group the client supports but sent no key
+    /// share for, so that it has to retry after a HelloRetryRequest
     #[arg(long)]
-    key: PathBuf,
+    force_hrr: bool,
+
+    /// Socket addresses for cleartext listeners serving HTTP/1.1 and h2c
+    #[arg(long)]
+    plain: Vec<SocketAddr>,
+
+    /// Read a PROXY protocol (v1 or v2) header sent by a load balancer in
+    /// front of pawprint before anything else on each connection
+    #[arg(long, value_enum)]
+    proxy_protocol: Option<ProxyProtocol>,
+
+    /// Number of sockets bound to each address with SO_REUSEPORT, each with
+    /// its own accept loop
+    #[arg(long, default_value_t = 1)]
+    workers: usize,
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
     let args = Args::parse();
-    let certs = load_certs(&args.certs)?;
-    let key = load_key(&args.key)?;
+    let addrs = args
+        .listeners
+        .iter()
+        .map(|listener| listener.addr)
+        .chain(args.plain.iter().copied())
+        .collect::<Vec<_>>();
+    let mut tasks = JoinSet::new();
 
-    let mut config = rustls::ServerConfig::builder()
-        .with_safe_defaults()
-        .with_no_client_auth()
-        .with_single_cert(certs, key)?;
-    config.alpn_protocols = vec!["h2".as_bytes().to_vec(), "http/1.1".as_bytes().to_vec()];
+    for spec in &args.listeners {
+        let certs = spec.certs.as_ref().or(args.certs.as_ref());
+        let key = spec.key.as_ref().or(args.key.as_ref());
+        let (Some(certs), Some(key)) = (certs, key) else {
+            return Err(anyhow::anyhow!(
+                "no certificate chain or private key for {}",
+                spec.addr
+            ));
+        };
+        let configs = tls_configs(certs, key, &spec.alpn, spec.force_hrr || args.force_hrr)?;
+        let configs = Arc::new(configs);
 
-    let acceptor = TlsAcceptor::from(Arc::new(config));
+        println!("🐾 Listening on {}", spec.addr);
+        for listener in bind_listeners(spec.addr, &addrs, args.workers)? {
+            tasks.spawn(listen_tls(listener, configs.clone(), args.proxy_protocol));
+        }
+    }
 
-    println!("🐾 Listening on {}", args.addr);
-    let listener = TcpListener::bind(&args.addr).await?;
+    for &addr in &args.plain {
+        println!("🐾 Listening on {addr} (cleartext)");
+        for listener in bind_listeners(addr, &addrs, args.workers)? {
+            tasks.spawn(listen_plain(listener, args.proxy_protocol));
+        }
+    }
 
-    loop {
-        let (stream, _peer_addr) = listener.accept().await?;
-        let acceptor = acceptor.clone();
+    while let Some(result) = tasks.join_next().await {
+        result??;
+    }
+    Ok(())
+}
 
-        let stream = TlsInspctor::new(stream);
+/// The server configurations of a TLS listener. With force-hrr, there is one
+/// more per key exchange group, offering only that group.
+struct TlsConfigs {
+    default: Arc<rustls::ServerConfig>,
+    retry: Vec<(u16, Arc<rustls::ServerConfig>)>,
+}
 
-        let fut = async move {
-            let stream = acceptor.accept(stream).await?;
-            let tls_report = stream.get_ref().0.client_hello().map(TlsReport::new);
-
-            tokio::task::spawn(async move {
-                let stream = Http2Inspector::new(stream);
-                let frames = stream.frames();
-                if let Err(http_err) = Http::new()
-                    .serve_connection(
-                        stream,
-                        service_fn(|req: Request<Body>| {
-                            let tls_report = tls_report.clone();
-                            let http2_report = Http2Report::new(&frames.lock().unwrap());
-                            let report = Report {
-                                tls: tls_report,
-                                http2: http2_report,
-                            };
-                            async move { handle_request(req, report).await }
-                        }),
-                    )
-                    .await
-                {
-                    eprintln!("Error while serving HTTP connection: {http_err}");
+impl TlsConfigs {
+    /// Picks the configuration for a ClientHello. With force-hrr, this is the
+    /// one offering the client's most preferred group that it did not send a
+    /// key share for, so that the server has to ask for one.
+    fn select(&self, hello: Option<&ClientHello>) -> Arc<rustls::ServerConfig> {
+        let mut groups = Vec::new();
+        let mut key_shares = Vec::new();
+        for ext in hello.into_iter().flat_map(|hello| hello.extensions()) {
+            match ext.payload() {
+                ExtensionPayload::SupportedGroups(list) => groups = list,
+                ExtensionPayload::KeyShare(entries) => {
+                    key_shares = entries.iter().map(|entry| entry.group).collect()
                 }
+                _ => {}
+            }
+        }
+        groups
+            .iter()
+            .filter(|group| !key_shares.contains(group))
+            .find_map(|group| self.retry.iter().find(|(id, _)| id == group))
+            .map_or(&self.default, |(_, config)| config)
+            .clone()
+    }
+}
+
+fn tls_configs(certs: &Path, key: &Path, alpn: &[String], force_hrr: bool) -> Result<TlsConfigs> {
+    let certs = load_certs(certs)?;
+    let key = load_key(key)?;
+
+    let config = |kx_groups: &[&'static rustls::SupportedKxGroup]| -> Result<_> {
+        let mut config = rustls::ServerConfig::builder()
+            .with_safe_default_cipher_suites()
+            .with_kx_groups(kx_groups)
+            .with_safe_default_protocol_versions()?
+            .with_no_client_auth()
+            .with_single_cert(certs.clone(), key.clone())?;
+        config.alpn_protocols = alpn
+            .iter()
+            .map(|protocol| protocol.as_bytes().to_vec())
+            .collect();
+        Ok(Arc::new(config))
+    };
+    let retry = if force_hrr {
+        rustls::ALL_KX_GROUPS
+            .iter()
+            .map(|group| Ok((group.name.get_u16(), config(&[group])?)))
+            .collect::<Result<_>>()?
+    } else {
+        Vec::new()
+    };
+    Ok(TlsConfigs {
+        default: config(&rustls::ALL_KX_GROUPS)?,
+        retry,
+    })
+}
+
+fn bind_listeners(
+    addr: SocketAddr,
+    addrs: &[SocketAddr],
+    workers: usize,
+) -> io::Result<Vec<TcpListener>> {
+    let listeners = bind(addr, workers, needs_v6_only(addr, addrs))?;
+    if let Some(err) = listeners
+        .iter()
+        .map(save_syn)
+        .find_map(|result| result.err())
+    {
+        eprintln!("TCP SYN fingerprinting is not available: {err}");
+    }
+    Ok(listeners)
+}
+
+async fn listen_tls(
+    listener: TcpListener,
+    configs: Arc<TlsConfigs>,
+    proxy_protocol: Option<ProxyProtocol>,
+) -> io::Result<()> {
+    loop {
+        let (stream, remote_addr) = listener.accept().await?;
+        let mut conn = Connection::new(&stream, remote_addr);
+        let configs = configs.clone();
+
+        let fut = async move {
+            let (stream, proxy) = accept_proxy(stream, proxy_protocol).await?;
+            conn.proxy = proxy;
+            conn.handshake_started();
+            let mut inspector = TlsInspctor::new(stream);
+            let prefix = if configs.retry.is_empty() {
+                Vec::new()
+            } else {
+                inspector.read_client_hello().await?
+            };
+            let hello = inspector.capture().client_hello();
+            let signature = SignatureRecord::default();
+            let config = signature.config(&configs.select(hello.as_ref().map(|(hello, _)| hello)));
+            let stream = TlsAcceptor::from(config)
+                .accept(Rewind::new(inspector, prefix))
+                .await?;
+            conn.handshake_done();
+            let (inspector, server_conn) = stream.get_ref();
+            let inspector = inspector.get_ref();
+            let capture = inspector.capture();
+            conn.tls = capture.client_hello().map(|(hello, fragmentation)| {
+                let hello_retry = capture
+                    .retried_client_hello()
+                    .map(|(retry, fragmentation)| {
+                        HelloRetryReport::new(&hello, &retry, fragmentation)
+                    });
+                let negotiated = Negotiated::new(server_conn, capture, &hello, &signature);
+                TlsReport::new(&hello, fragmentation, hello_retry, negotiated)
             });
 
+            tokio::task::spawn(serve(stream, conn));
+
             Ok(()) as io::Result<()>
         };
 
@@ -99,6 +230,138 @@ async fn main() -> Result<()> {
     }
 }
 
+async fn listen_plain(
+    listener: TcpListener,
+    proxy_protocol: Option<ProxyProtocol>,
+) -> io::Result<()> {
+    loop {
+        let (stream, remote_addr) = listener.accept().await?;
+        let mut conn = Connection::new(&stream, remote_addr);
+        tokio::spawn(async move {
+            match accept_proxy(stream, proxy_protocol).await {
+                Ok((stream, proxy)) => {
+                    conn.proxy = proxy;
+                    serve(stream, conn).await
+                }
+                Err(err) => eprintln!("Error: {err:?}"),
+            }
+        });
+    }
+}
+
+/// Serves HTTP/1.1 or HTTP/2 on a connection, inspecting what the client
+/// sends. Cleartext HTTP/2 is detected from the connection preface, or
+/// switched to with `Upgrade: h2c`.
+async fn serve<S>(stream: S, conn: Connection)
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    let stream = Http1Inspector::new(Http2Inspector::new(stream));
+    let requests = stream.capture().clone();
+    let frames = stream.get_ref().capture().clone();
+    let conn = Arc::new(conn);
+    let mut requests_served = 0;
+    if let Err(http_err) = Http::new()
+        .serve_connection(
+            stream,
+            service_fn(|mut req: Request<Body>| {
+                requests_served += 1;
+                let http1 = match req.version() {
+                    Version::HTTP_2 => None,
+                    _ => requests.lock().unwrap().pop_front(),
+                };
+                let upgrade = conn.tls.is_none()
+                    && http1
+                        .as_ref()
+                        .is_some_and(|request| request.accepts_h2c_upgrade());
+                let report = if upgrade {
+                    let request = upgrade_request(&req);
+                    let upgrade = hyper::upgrade::on(&mut req);
+                    tokio::spawn(serve_h2c(
+                        upgrade,
+                        request,
+                        conn.clone(),
+                        requests_served - 1,
+                        http1,
+                    ));
+                    None
+                } else {
+                    Some(report(&conn, requests_served, &frames, &req, http1))
+                };
+                async move {
+                    match report {
+                        Some(report) => handle_request(req, report).await,
+                        None => Ok(switching_protocols()),
+                    }
+                }
+            }),
+        )
+        .with_upgrades()
+        .await
+    {
+        eprintln!("Error while serving HTTP connection: {http_err}");
+    }
+}
+
+/// Serves the HTTP/2 connection a client switched to with `Upgrade: h2c`,
+/// where the request that asked for it is answered on stream 1 and reported
+/// with the HTTP/1 head it was sent as.
+async fn serve_h2c(
+    upgrade: OnUpgrade,
+    request: Vec<u8>,
+    conn: Arc<Connection>,
+    mut requests_served: usize,
+    mut head: Option<Http1Request>,
+) {
+    let result = async {
+        let stream = Http2Inspector::new(upgrade.await.map_err(io::Error::other)?);
+        let frames = stream.capture().clone();
+        let stream = accept_h2c(stream, request).await?;
+        Http::new()
+            .http2_only(true)
+            .serve_connection(
+                stream,
+                service_fn(|req: Request<Body>| {
+                    requests_served += 1;
+                    let report = report(&conn, requests_served, &frames, &req, head.take());
+                    async move { handle_request(req, report).await }
+                }),
+            )
+            .await
+            .map_err(io::Error::other)
+    };
+    if let Err(err) = result.await {
+        eprintln!("Error while serving h2c connection: {err}");
+    }
+}
+
+/// Builds the report on a request from what was captured on its connection.
+fn report(
+    conn: &Connection,
+    requests_served: usize,
+    frames: &Mutex<Http2Frames>,
+    req: &Request<Body>,
+    http1: Option<Http1Request>,
+) -> Report {
+    let mut frames = frames.lock().unwrap();
+    let path = req.uri().path_and_query().map_or("/", |path| path.as_str());
+    let request = frames.take_request(req.method().as_str(), path);
+    Report {
+        connection: ConnectionReport::new(conn, requests_served),
+        tls: conn.tls.clone(),
+        http2: Http2Report::new(&mut frames, request),
+        http1: http1.map(Http1Report::new),
+        // Behind a PROXY protocol header, the TCP connection is the load
+        // balancer's rather than the client's.
+        tcp: conn
+            .proxy
+            .as_ref()
+            .map_or(true, |proxy| !proxy.proxied)
+            .then(|| TcpReport::new(&conn.tcp)),
+        proxy: conn.proxy.clone(),
+    }
+}
+
 fn load_certs(path: &Path) -> io::Result<Vec<Certificate>> {
     let mut reader = BufReader::new(File::open(path)?);
     let certs = rustls_pemfile::certs(&mut reader)?;
@@ -0,0 +1,146 @@
+use crate::handshake::{ClientHello, EXT_EARLY_DATA};
+use crate::iana::{self, Named};
+use crate::tls::TlsCapture;
+use serde_derive::Serialize;
+use std::sync::{Arc, Mutex};
+use tokio_rustls::rustls::server::{self, ResolvesServerCert};
+use tokio_rustls::rustls::{
+    sign, Error, ServerConfig, ServerConnection, SignatureAlgorithm, SignatureScheme,
+};
+
+const TLS13: u16 = 0x0304;
+
+#[derive(Clone, Serialize)]
+pub struct Negotiated {
+    pub version: Option<Named<u16>>,
+    pub cipher_suite: Option<Named<u16>>,
+    pub key_exchange_group: Option<Named<u16>>,
+    pub signature_scheme: Option<Named<u16>>,
+    pub alpn: Option<String>,
+    pub sni: Option<String>,
+    pub resumed: bool,
+    pub early_data_offered: bool,
+}
+
+impl Negotiated {
+    pub fn new(
+        conn: &ServerConnection,
+        capture: &TlsCapture,
+        hello: &ClientHello,
+        signature: &SignatureRecord,
+    ) -> Self {
+        let version = conn.protocol_version().map(|v| v.get_u16());
+        let server_hello = capture.server_hello();
+        let key_exchange = capture.server_key_exchange();
+
+        let resumed = match (&server_hello, version) {
+            (Some(server_hello), Some(TLS13)) => server_hello.has_pre_shared_key(),
+            (Some(server_hello), _) => {
+                !hello.session_id.is_empty() && server_hello.session_id == hello.session_id
+            }
+            (None, _) => false,
+        };
+
+        let key_exchange_group = match version {
+            Some(TLS13) => server_hello.and_then(|hello| hello.key_share_group()),
+            _ => key_exchange.as_ref().map(|kx| kx.named_group),
+        };
+
+        let signature_scheme = match version {
+            Some(TLS13) => signature.scheme().map(|scheme| scheme.get_u16()),
+            _ => key_exchange.map(|kx| kx.signature_scheme),
+        };
+
+        Self {
+            version: version.map(iana::version),
+            cipher_suite: conn
+                .negotiated_cipher_suite()
+                .map(|suite| iana::cipher_suite(suite.suite().get_u16())),
+            key_exchange_group: key_exchange_group.map(iana::named_group),
+            signature_scheme: signature_scheme.map(iana::signature_scheme),
+            alpn: conn
+                .alpn_protocol()
+                .map(|protocol| String::from_utf8_lossy(protocol).into_owned()),
+            sni: conn.sni_hostname().map(str::to_string),
+            resumed,
+            early_data_offered: hello.extensions().any(|ext| ext.ty == EXT_EARLY_DATA),
+        }
+    }
+}
+
+/// The signature scheme the server signed the handshake with, recorded as
+/// rustls signs it. The TLS 1.3 CertificateVerify is encrypted, so unlike the
+/// TLS 1.2 ServerKeyExchange it cannot be read off the wire.
+#[derive(Clone, Default)]
+pub struct SignatureRecord(Arc<Mutex<Option<SignatureScheme>>>);
+
+impl SignatureRecord {
+    /// Returns a copy of `config` whose certificate keys record their
+    /// signatures here.
+    pub fn config(&self, config: &ServerConfig) -> Arc<ServerConfig> {
+        let mut config = config.clone();
+        config.cert_resolver = Arc::new(RecordingResolver {
+            inner: config.cert_resolver.clone(),
+            record: self.clone(),
+        });
+        Arc::new(config)
+    }
+
+    pub fn scheme(&self) -> Option<SignatureScheme> {
+        *self.0.lock().unwrap()
+    }
+}
+
+struct RecordingResolver {
+    inner: Arc<dyn ResolvesServerCert>,
+    record: SignatureRecord,
+}
+
+impl ResolvesServerCert for RecordingResolver {
+    fn resolve(&self, client_hello: server::ClientHello) -> Option<Arc<sign::CertifiedKey>> {
+        let key = self.inner.resolve(client_hello)?;
+        Some(Arc::new(sign::CertifiedKey {
+            key: Arc::new(RecordingKey {
+                inner: key.key.clone(),
+                record: self.record.clone(),
+            }),
+            ..(*key).clone()
+        }))
+    }
+}
+
+struct RecordingKey {
+    inner: Arc<dyn sign::SigningKey>,
+    record: SignatureRecord,
+}
+
+impl sign::SigningKey for RecordingKey {
+    fn choose_scheme(&self, offered: &[SignatureScheme]) -> Option<Box<dyn sign::Signer>> {
+        let inner = self.inner.choose_scheme(offered)?;
+        Some(Box::new(RecordingSigner {
+            inner,
+            record: self.record.clone(),
+        }))
+    }
+
+    fn algorithm(&self) -> SignatureAlgorithm {
+        self.inner.algorithm()
+    }
+}
+
+struct RecordingSigner {
+    inner: Box<dyn sign::Signer>,
+    record: SignatureRecord,
+}
+
+impl sign::Signer for RecordingSigner {
+    fn sign(&self, message: &[u8]) -> Result<Vec<u8>, Error> {
+        let signature = self.inner.sign(message)?;
+        *self.record.0.lock().unwrap() = Some(self.inner.scheme());
+        Ok(signature)
+    }
+
+    fn scheme(&self) -> SignatureScheme {
+        self.inner.scheme()
+    }
+}
@@ -0,0 +1,70 @@
+use serde_derive::Serialize;
+
+use crate::http2::{ExtensiblePriority, Frame, PriorityUpdateFrame};
+
+const SETTINGS_NO_RFC7540_PRIORITIES: u16 = 0x9;
+
+/// RFC 9218 extensible priorities: the SETTINGS_NO_RFC7540_PRIORITIES value
+/// and `priority` header of the first request, and the PRIORITY_UPDATE frames
+/// received on the connection so far.
+#[derive(Clone, Serialize)]
+pub struct Priorities {
+    pub sha1: String,
+    pub text: String,
+    pub no_rfc7540_priorities: Option<u32>,
+    pub priority_header: Option<ExtensiblePriority>,
+    pub priority_updates: Vec<(u32, ExtensiblePriority)>,
+}
+
+impl Priorities {
+    pub fn new(frames: &[Frame], updates: &[PriorityUpdateFrame]) -> Self {
+        let mut no_rfc7540_priorities = None;
+        let mut priority_header = None;
+        for frame in frames {
+            match frame {
+                Frame::Settings(frame) => {
+                    for item in &frame.settings {
+                        if item.id == SETTINGS_NO_RFC7540_PRIORITIES {
+                            no_rfc7540_priorities = Some(item.value);
+                        }
+                    }
+                }
+                Frame::Headers(frame) => {
+                    priority_header = frame
+                        .headers
+                        .iter()
+                        .find(|header| header.name == "priority")
+                        .map(|header| ExtensiblePriority::parse(&header.value));
+                }
+                _ => (),
+            }
+        }
+
+        let priority_updates = updates
+            .iter()
+            .map(|frame| (frame.prioritized_stream_id, frame.priority.clone()))
+            .collect::<Vec<_>>();
+
+        let settings = no_rfc7540_priorities.map_or("-".to_string(), |value| value.to_string());
+        let header = priority_header
+            .as_ref()
+            .map_or("-".to_string(), |priority| priority.to_string());
+        let updates = if priority_updates.is_empty() {
+            "0".to_string()
+        } else {
+            priority_updates
+                .iter()
+                .map(|(stream_id, priority)| format!("{stream_id}:{priority}"))
+                .collect::<Vec<_>>()
+                .join(";")
+        };
+        let text = format!("{settings}|{header}|{updates}");
+        Self {
+            sha1: sha1_smol::Sha1::from(&text).hexdigest(),
+            text,
+            no_rfc7540_priorities,
+            priority_header,
+            priority_updates,
+        }
+    }
+}
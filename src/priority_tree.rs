@@ -0,0 +1,312 @@
+use serde_derive::Serialize;
+use std::collections::BTreeMap;
+
+use crate::http2::{HeadersFrame, Priority};
+
+/// Default weight of a stream, as sent on the wire (weight - 1).
+const DEFAULT_WEIGHT: u8 = 15;
+
+/// Limits on the streams kept per connection, since PRIORITY frames are cheap
+/// to send and may name any number of idle streams.
+const MAX_NODES: usize = 256;
+const MAX_DEPTH: usize = 32;
+
+/// The RFC 7540 dependency tree of a connection, rebuilt from the priority
+/// carried on HEADERS and PRIORITY frames. Once the tree grows past
+/// `MAX_NODES` streams or `MAX_DEPTH` levels, further frames are ignored.
+#[derive(Default)]
+pub struct PriorityTree {
+    nodes: BTreeMap<u32, Node>,
+    truncated: bool,
+    /// The report on the tree, built on demand and kept until it changes.
+    report: Option<PriorityTreeReport>,
+}
+
+struct Node {
+    parent: u32,
+    weight: u8,
+    exclusive: bool,
+    request: Option<StreamRequest>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct StreamRequest {
+    pub path: String,
+    pub destination: Option<String>,
+}
+
+impl Node {
+    fn new() -> Self {
+        Self {
+            parent: 0,
+            weight: DEFAULT_WEIGHT,
+            exclusive: false,
+            request: None,
+        }
+    }
+}
+
+impl PriorityTree {
+    pub fn headers(&mut self, frame: &HeadersFrame) {
+        self.report = None;
+        if !self.reserve(&[frame.stream_id]) {
+            return;
+        }
+        let value = |name| {
+            frame
+                .headers
+                .iter()
+                .find(|header| header.name == name)
+                .map(|header| header.value.clone())
+        };
+        let node = self.nodes.entry(frame.stream_id).or_insert_with(Node::new);
+        if node.request.is_none() {
+            node.request = Some(StreamRequest {
+                path: value(":path").unwrap_or_default(),
+                destination: value("sec-fetch-dest"),
+            });
+        }
+        if let Some(priority) = &frame.priority {
+            self.prioritize(frame.stream_id, priority);
+        }
+    }
+
+    /// Applies a reprioritization as described in RFC 7540, section 5.3.3.
+    pub fn prioritize(&mut self, stream_id: u32, priority: &Priority) {
+        self.report = None;
+        let parent = priority.dep_stream_id;
+        if stream_id == 0 || parent == stream_id || !self.reserve(&[stream_id, parent]) {
+            return;
+        }
+        self.nodes.entry(stream_id).or_insert_with(Node::new);
+        if parent != 0 {
+            self.nodes.entry(parent).or_insert_with(Node::new);
+        }
+
+        // A stream cannot depend on its own descendant: the descendant is
+        // moved up to the stream's former parent first.
+        if self.is_descendant(parent, stream_id) {
+            let former_parent = self.nodes[&stream_id].parent;
+            if let Some(node) = self.nodes.get_mut(&parent) {
+                node.parent = former_parent;
+            }
+        }
+        if priority.exclusive {
+            for (id, node) in self.nodes.iter_mut() {
+                if node.parent == parent && *id != stream_id {
+                    node.parent = stream_id;
+                }
+            }
+        }
+        if let Some(node) = self.nodes.get_mut(&stream_id) {
+            node.parent = parent;
+            node.weight = priority.weight;
+            node.exclusive = priority.exclusive;
+        }
+        if self.depth_exceeded() {
+            self.truncated = true;
+        }
+    }
+
+    /// Checks that the streams can be added without going over `MAX_NODES`,
+    /// and stops recording otherwise.
+    fn reserve(&mut self, stream_ids: &[u32]) -> bool {
+        if self.truncated {
+            return false;
+        }
+        let mut new_ids = stream_ids
+            .iter()
+            .filter(|id| **id != 0 && !self.nodes.contains_key(id))
+            .collect::<Vec<_>>();
+        new_ids.dedup();
+        if self.nodes.len() + new_ids.len() > MAX_NODES {
+            self.truncated = true;
+        }
+        !self.truncated
+    }
+
+    fn depth_exceeded(&self) -> bool {
+        let mut depths = BTreeMap::from([(0, 0)]);
+        for &stream_id in self.nodes.keys() {
+            let mut chain = Vec::new();
+            let mut id = stream_id;
+            let depth = loop {
+                if let Some(&depth) = depths.get(&id) {
+                    break depth;
+                }
+                if chain.len() > MAX_DEPTH {
+                    return true;
+                }
+                chain.push(id);
+                id = self.nodes.get(&id).map_or(0, |node| node.parent);
+            };
+            for (i, id) in chain.iter().rev().enumerate() {
+                depths.insert(*id, depth + i + 1);
+            }
+            if depth + chain.len() > MAX_DEPTH {
+                return true;
+            }
+        }
+        false
+    }
+
+    fn is_descendant(&self, mut stream_id: u32, ancestor: u32) -> bool {
+        for _ in 0..self.nodes.len() {
+            match self.nodes.get(&stream_id) {
+                Some(node) if node.parent == ancestor => return true,
+                Some(node) => stream_id = node.parent,
+                None => return false,
+            }
+        }
+        false
+    }
+
+    /// Returns the report on the tree as it stands, only building it again
+    /// after the tree has changed.
+    pub fn report(&mut self) -> &PriorityTreeReport {
+        if self.report.is_none() {
+            self.report = Some(PriorityTreeReport::new(self));
+        }
+        self.report.as_ref().unwrap()
+    }
+
+    /// Builds the tree below the root stream 0, leaving out any levels past
+    /// `MAX_DEPTH`.
+    fn root(&self) -> PriorityNode {
+        let mut children = BTreeMap::<u32, Vec<u32>>::new();
+        for (id, node) in &self.nodes {
+            children.entry(node.parent).or_default().push(*id);
+        }
+
+        // Visit the streams in pre-order, then build each node after its
+        // children by going through them backwards.
+        let mut order = Vec::new();
+        let mut stack = vec![(0, 0)];
+        while let Some((stream_id, depth)) = stack.pop() {
+            order.push(stream_id);
+            if depth < MAX_DEPTH {
+                let ids = children.get(&stream_id).into_iter().flatten();
+                stack.extend(ids.map(|id| (*id, depth + 1)));
+            }
+        }
+        let mut built = BTreeMap::<u32, Vec<PriorityNode>>::new();
+        let mut root = None;
+        for stream_id in order.into_iter().rev() {
+            let children = built.remove(&stream_id).unwrap_or_default();
+            let Some(node) = self.nodes.get(&stream_id) else {
+                root = Some(PriorityNode {
+                    stream_id,
+                    weight: DEFAULT_WEIGHT,
+                    exclusive: false,
+                    request: None,
+                    children,
+                });
+                continue;
+            };
+            built.entry(node.parent).or_default().push(PriorityNode {
+                stream_id,
+                weight: node.weight,
+                exclusive: node.exclusive,
+                request: node.request.clone(),
+                children,
+            });
+        }
+        root.unwrap_or_else(|| PriorityNode {
+            stream_id: 0,
+            weight: DEFAULT_WEIGHT,
+            exclusive: false,
+            request: None,
+            children: Vec::new(),
+        })
+    }
+}
+
+#[derive(Clone, Serialize)]
+pub struct PriorityNode {
+    pub stream_id: u32,
+    pub weight: u8,
+    pub exclusive: bool,
+    /// The request opened on the stream, or `None` for idle streams that only
+    /// appear in PRIORITY frames.
+    pub request: Option<StreamRequest>,
+    pub children: Vec<PriorityNode>,
+}
+
+impl PriorityNode {
+    /// Returns the nodes below this one in depth-first order, with their depth.
+    pub fn descendants(&self) -> Vec<(usize, &PriorityNode)> {
+        let mut nodes = Vec::new();
+        let mut stack = self
+            .children
+            .iter()
+            .rev()
+            .map(|node| (0, node))
+            .collect::<Vec<_>>();
+        while let Some((depth, node)) = stack.pop() {
+            nodes.push((depth, node));
+            stack.extend(node.children.iter().rev().map(|child| (depth + 1, child)));
+        }
+        nodes
+    }
+}
+
+/// Describes the shape of the subtrees, leaving out stream ids and paths.
+fn shape(nodes: &[PriorityNode]) -> String {
+    enum Step<'a> {
+        Node(&'a PriorityNode),
+        Text(&'static str),
+    }
+
+    fn push_nodes<'a>(stack: &mut Vec<Step<'a>>, nodes: &'a [PriorityNode]) {
+        for (i, node) in nodes.iter().enumerate().rev() {
+            stack.push(Step::Node(node));
+            if i > 0 {
+                stack.push(Step::Text(","));
+            }
+        }
+    }
+
+    let mut text = String::new();
+    let mut stack = Vec::new();
+    push_nodes(&mut stack, nodes);
+    while let Some(step) = stack.pop() {
+        let node = match step {
+            Step::Node(node) => node,
+            Step::Text(value) => {
+                text.push_str(value);
+                continue;
+            }
+        };
+        let kind = match &node.request {
+            Some(request) => request.destination.as_deref().unwrap_or("request"),
+            None => "idle",
+        };
+        let exclusive = if node.exclusive { "e" } else { "" };
+        text.push_str(&format!("{}{exclusive}:{kind}[", node.weight));
+        stack.push(Step::Text("]"));
+        push_nodes(&mut stack, &node.children);
+    }
+    text
+}
+
+#[derive(Clone, Serialize)]
+pub struct PriorityTreeReport {
+    pub sha1: String,
+    pub text: String,
+    pub root: PriorityNode,
+    /// Whether the client sent more priorities than are kept per connection.
+    pub truncated: bool,
+}
+
+impl PriorityTreeReport {
+    pub fn new(tree: &PriorityTree) -> Self {
+        let root = tree.root();
+        let text = shape(&root.children);
+        Self {
+            sha1: sha1_smol::Sha1::from(&text).hexdigest(),
+            text,
+            root,
+            truncated: tree.truncated,
+        }
+    }
+}
@@ -0,0 +1,485 @@
+use serde_derive::Serialize;
+use std::io::IoSlice;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::pin::Pin;
+use std::task;
+use std::task::Poll;
+use std::time::Duration;
+use tokio::io::{self, AsyncRead, AsyncReadExt, AsyncWrite, ReadBuf};
+
+const V1_PREFIX: &[u8] = b"PROXY ";
+const V1_MAX_LEN: usize = 107;
+const V2_SIGNATURE: &[u8] = b"\r\n\r\n\0\r\nQUIT\n";
+const V2_HEADER_LEN: usize = 16;
+const HEADER_TIMEOUT: Duration = Duration::from_secs(10);
+
+const PP2_TYPE_ALPN: u8 = 0x01;
+const PP2_TYPE_AUTHORITY: u8 = 0x02;
+const PP2_TYPE_CRC32C: u8 = 0x03;
+const PP2_TYPE_NOOP: u8 = 0x04;
+const PP2_TYPE_UNIQUE_ID: u8 = 0x05;
+const PP2_TYPE_SSL: u8 = 0x20;
+const PP2_TYPE_NETNS: u8 = 0x30;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ProxyProtocol {
+    /// Parse a header when one is sent. Clients connecting directly can then
+    /// claim any source address
+    Optional,
+    /// Reject connections without a header
+    Required,
+}
+
+/// A HAProxy PROXY protocol header (v1 or v2).
+#[derive(Clone, Serialize)]
+pub struct ProxyHeader {
+    pub version: u8,
+    /// `false` for v2 LOCAL commands and v1 UNKNOWN, where the addresses are
+    /// those of the connection itself.
+    pub proxied: bool,
+    pub source: Option<SocketAddr>,
+    pub destination: Option<SocketAddr>,
+    pub tlvs: Vec<ProxyTlv>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct ProxyTlv {
+    #[serde(rename = "type")]
+    pub ty: u8,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<&'static str>,
+    pub value: String,
+}
+
+/// Reads a PROXY protocol header from the start of `stream`. Whatever was read
+/// past the header, or instead of it, is replayed by the returned stream.
+pub async fn accept_proxy<S>(
+    stream: S,
+    mode: Option<ProxyProtocol>,
+) -> io::Result<(Rewind<S>, Option<ProxyHeader>)>
+where
+    S: AsyncRead + Unpin,
+{
+    let Some(mode) = mode else {
+        return Ok((Rewind::new(stream, Vec::new()), None));
+    };
+    tokio::time::timeout(HEADER_TIMEOUT, read_header(stream, mode))
+        .await
+        .unwrap_or_else(|_| Err(io::ErrorKind::TimedOut.into()))
+}
+
+async fn read_header<S>(
+    mut stream: S,
+    mode: ProxyProtocol,
+) -> io::Result<(Rewind<S>, Option<ProxyHeader>)>
+where
+    S: AsyncRead + Unpin,
+{
+    let mut buf = Vec::new();
+    let header_len = loop {
+        let prefix = |signature: &[u8]| {
+            let len = buf.len().min(signature.len());
+            buf[..len] == signature[..len]
+        };
+        if prefix(V2_SIGNATURE) {
+            if buf.len() >= V2_HEADER_LEN {
+                break V2_HEADER_LEN + u16::from_be_bytes([buf[14], buf[15]]) as usize;
+            }
+        } else if prefix(V1_PREFIX) {
+            if let Some(end) = buf.windows(2).position(|w| w == b"\r\n") {
+                break end + 2;
+            }
+            if buf.len() >= V1_MAX_LEN {
+                return Err(invalid_data("PROXY v1 header is too long"));
+            }
+        } else {
+            if mode == ProxyProtocol::Required {
+                return Err(invalid_data("PROXY header is missing"));
+            }
+            return Ok((Rewind::new(stream, buf), None));
+        }
+        if read_more(&mut stream, &mut buf).await? == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+    };
+
+    while buf.len() < header_len {
+        if read_more(&mut stream, &mut buf).await? == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+    }
+    let rest = buf.split_off(header_len);
+    let header = if buf.starts_with(V2_SIGNATURE) {
+        ProxyHeader::parse_v2(&buf)
+    } else {
+        ProxyHeader::parse_v1(&buf)
+    }
+    .ok_or_else(|| invalid_data("malformed PROXY header"))?;
+    Ok((Rewind::new(stream, rest), Some(header)))
+}
+
+async fn read_more<S: AsyncRead + Unpin>(stream: &mut S, buf: &mut Vec<u8>) -> io::Result<usize> {
+    let mut chunk = [0; 512];
+    let len = stream.read(&mut chunk).await?;
+    buf.extend(&chunk[..len]);
+    Ok(len)
+}
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+impl ProxyHeader {
+    fn parse_v1(header: &[u8]) -> Option<Self> {
+        let line = std::str::from_utf8(header).ok()?.strip_suffix("\r\n")?;
+        let mut fields = line.split(' ').skip(1);
+        let proxied = match fields.next()? {
+            "TCP4" | "TCP6" => true,
+            "UNKNOWN" => false,
+            _ => return None,
+        };
+        let (source, destination) = if proxied {
+            let src = fields.next()?.parse::<IpAddr>().ok()?;
+            let dst = fields.next()?.parse::<IpAddr>().ok()?;
+            let src_port = fields.next()?.parse().ok()?;
+            let dst_port = fields.next()?.parse().ok()?;
+            (
+                Some(SocketAddr::new(src, src_port)),
+                Some(SocketAddr::new(dst, dst_port)),
+            )
+        } else {
+            (None, None)
+        };
+        Some(Self {
+            version: 1,
+            proxied,
+            source,
+            destination,
+            tlvs: Vec::new(),
+        })
+    }
+
+    fn parse_v2(header: &[u8]) -> Option<Self> {
+        let &[command, family, len_hi, len_lo] = header.get(12..V2_HEADER_LEN)? else {
+            return None;
+        };
+        if header.len() != V2_HEADER_LEN + u16::from_be_bytes([len_hi, len_lo]) as usize {
+            return None;
+        }
+        let version = command >> 4;
+        let proxied = match command & 0x0f {
+            0 => false,
+            1 => true,
+            _ => return None,
+        };
+        if version != 2 {
+            return None;
+        }
+        let mut data = header.get(V2_HEADER_LEN..)?;
+        let (source, destination) = match family >> 4 {
+            // AF_INET
+            1 if data.len() >= 12 => {
+                let src = Ipv4Addr::from(<[u8; 4]>::try_from(&data[..4]).ok()?);
+                let dst = Ipv4Addr::from(<[u8; 4]>::try_from(&data[4..8]).ok()?);
+                let ports = &data[8..12];
+                data = &data[12..];
+                addresses(src.into(), dst.into(), ports)
+            }
+            // AF_INET6
+            2 if data.len() >= 36 => {
+                let src = Ipv6Addr::from(<[u8; 16]>::try_from(&data[..16]).ok()?);
+                let dst = Ipv6Addr::from(<[u8; 16]>::try_from(&data[16..32]).ok()?);
+                let ports = &data[32..36];
+                data = &data[36..];
+                addresses(src.into(), dst.into(), ports)
+            }
+            // AF_UNIX addresses are skipped along with their TLVs.
+            3 if data.len() >= 216 => {
+                data = &data[216..];
+                (None, None)
+            }
+            0 => (None, None),
+            _ => return None,
+        };
+
+        let mut tlvs = Vec::new();
+        while let Some(&[ty, len_hi, len_lo]) = data.get(..3) {
+            let len = u16::from_be_bytes([len_hi, len_lo]) as usize;
+            let value = data.get(3..3 + len)?;
+            tlvs.push(ProxyTlv::new(ty, value));
+            data = &data[3 + len..];
+        }
+        if !data.is_empty() {
+            return None;
+        }
+        Some(Self {
+            version,
+            proxied,
+            source: source.filter(|_| proxied),
+            destination: destination.filter(|_| proxied),
+            tlvs,
+        })
+    }
+}
+
+fn addresses(src: IpAddr, dst: IpAddr, ports: &[u8]) -> (Option<SocketAddr>, Option<SocketAddr>) {
+    let src_port = u16::from_be_bytes([ports[0], ports[1]]);
+    let dst_port = u16::from_be_bytes([ports[2], ports[3]]);
+    (
+        Some(SocketAddr::new(src, src_port)),
+        Some(SocketAddr::new(dst, dst_port)),
+    )
+}
+
+impl ProxyTlv {
+    fn new(ty: u8, value: &[u8]) -> Self {
+        let name = match ty {
+            PP2_TYPE_ALPN => Some("ALPN"),
+            PP2_TYPE_AUTHORITY => Some("AUTHORITY"),
+            PP2_TYPE_CRC32C => Some("CRC32C"),
+            PP2_TYPE_NOOP => Some("NOOP"),
+            PP2_TYPE_UNIQUE_ID => Some("UNIQUE_ID"),
+            PP2_TYPE_SSL => Some("SSL"),
+            PP2_TYPE_NETNS => Some("NETNS"),
+            _ => None,
+        };
+        let value = match ty {
+            PP2_TYPE_ALPN | PP2_TYPE_AUTHORITY | PP2_TYPE_NETNS => {
+                String::from_utf8_lossy(value).into_owned()
+            }
+            _ => hex::encode(value),
+        };
+        Self { ty, name, value }
+    }
+}
+
+pin_project_lite::pin_project! {
+    /// A stream that first returns bytes already read from `inner`. They are
+    /// returned together with whatever `inner` has ready, so that the reads
+    /// seen by the layers above match those of the connection.
+    pub struct Rewind<S> {
+        #[pin]
+        inner: S,
+        prefix: Vec<u8>,
+    }
+}
+
+impl<S> Rewind<S> {
+    pub fn new(inner: S, prefix: Vec<u8>) -> Self {
+        Self { inner, prefix }
+    }
+
+    pub fn get_ref(&self) -> &S {
+        &self.inner
+    }
+}
+
+impl<S: AsyncRead> AsyncRead for Rewind<S> {
+    #[inline]
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut task::Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let me = self.project();
+        if me.prefix.is_empty() {
+            return me.inner.poll_read(cx, buf);
+        }
+        let len = me.prefix.len().min(buf.remaining());
+        buf.put_slice(&me.prefix[..len]);
+        me.prefix.drain(..len);
+        if me.prefix.is_empty() && buf.remaining() > 0 {
+            // Errors and EOF are left for the next read to report.
+            let _ = me.inner.poll_read(cx, buf);
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<S: AsyncWrite> AsyncWrite for Rewind<S> {
+    #[inline]
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut task::Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        self.project().inner.poll_write(cx, buf)
+    }
+
+    #[inline]
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut task::Context<'_>,
+        bufs: &[IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        self.project().inner.poll_write_vectored(cx, bufs)
+    }
+
+    #[inline]
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<io::Result<()>> {
+        self.project().inner.poll_flush(cx)
+    }
+
+    #[inline]
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<io::Result<()>> {
+        self.project().inner.poll_shutdown(cx)
+    }
+
+    #[inline]
+    fn is_write_vectored(&self) -> bool {
+        self.inner.is_write_vectored()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const V1_TCP4: &[u8] = b"PROXY TCP4 192.168.0.1 192.168.0.11 56324 443\r\n";
+    const V1_TCP6: &[u8] = b"PROXY TCP6 2001:db8::1 2001:db8::2 56324 443\r\n";
+
+    /// A v2 PROXY command over TCP/IPv4 with ALPN, AUTHORITY and UNIQUE_ID
+    /// TLVs, as sent by HAProxy.
+    const V2_TCP4: &[u8] = &[
+        0x0d, 0x0a, 0x0d, 0x0a, 0x00, 0x0d, 0x0a, 0x51, 0x55, 0x49, 0x54, 0x0a, 0x21, 0x11, 0x00,
+        0x24, 0xc6, 0x33, 0x64, 0x09, 0xc0, 0x00, 0x02, 0x01, 0x9c, 0x40, 0x01, 0xbb, 0x01, 0x00,
+        0x02, 0x68, 0x32, 0x02, 0x00, 0x0b, 0x65, 0x78, 0x61, 0x6d, 0x70, 0x6c, 0x65, 0x2e, 0x63,
+        0x6f, 0x6d, 0x05, 0x00, 0x02, 0x01, 0x02,
+    ];
+
+    /// A v2 LOCAL command, as sent by HAProxy health checks.
+    const V2_LOCAL: &[u8] = &[
+        0x0d, 0x0a, 0x0d, 0x0a, 0x00, 0x0d, 0x0a, 0x51, 0x55, 0x49, 0x54, 0x0a, 0x20, 0x00, 0x00,
+        0x00,
+    ];
+
+    #[test]
+    fn parse_v1_header() {
+        let header = ProxyHeader::parse_v1(V1_TCP4).unwrap();
+        assert!(header.proxied);
+        assert_eq!(header.source, Some("192.168.0.1:56324".parse().unwrap()));
+        assert_eq!(
+            header.destination,
+            Some("192.168.0.11:443".parse().unwrap())
+        );
+
+        let header = ProxyHeader::parse_v1(V1_TCP6).unwrap();
+        assert_eq!(header.source, Some("[2001:db8::1]:56324".parse().unwrap()));
+
+        let header = ProxyHeader::parse_v1(b"PROXY UNKNOWN\r\n").unwrap();
+        assert!(!header.proxied);
+        assert_eq!(header.source, None);
+    }
+
+    #[test]
+    fn reject_malformed_v1_header() {
+        for len in 0..V1_TCP4.len() {
+            assert!(ProxyHeader::parse_v1(&V1_TCP4[..len]).is_none());
+        }
+        for header in [
+            &b"PROXY TCP5 192.168.0.1 192.168.0.11 56324 443\r\n"[..],
+            b"PROXY TCP4 192.168.0.256 192.168.0.11 56324 443\r\n",
+            b"PROXY TCP4 192.168.0.1 192.168.0.11 65536 443\r\n",
+            b"PROXY TCP4 192.168.0.1 192.168.0.11 56324\r\n",
+            b"PROXY TCP4 \xff 192.168.0.11 56324 443\r\n",
+        ] {
+            assert!(ProxyHeader::parse_v1(header).is_none());
+        }
+    }
+
+    #[test]
+    fn parse_v2_header() {
+        let header = ProxyHeader::parse_v2(V2_TCP4).unwrap();
+        assert_eq!(header.version, 2);
+        assert!(header.proxied);
+        assert_eq!(header.source, Some("198.51.100.9:40000".parse().unwrap()));
+        assert_eq!(header.destination, Some("192.0.2.1:443".parse().unwrap()));
+        let tlvs = header
+            .tlvs
+            .iter()
+            .map(|tlv| (tlv.name, tlv.value.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            tlvs,
+            [
+                (Some("ALPN"), "h2"),
+                (Some("AUTHORITY"), "example.com"),
+                (Some("UNIQUE_ID"), "0102"),
+            ]
+        );
+
+        let header = ProxyHeader::parse_v2(V2_LOCAL).unwrap();
+        assert!(!header.proxied);
+        assert_eq!(header.source, None);
+        assert!(header.tlvs.is_empty());
+    }
+
+    #[test]
+    fn reject_malformed_v2_header() {
+        for len in 0..V2_TCP4.len() {
+            assert!(ProxyHeader::parse_v2(&V2_TCP4[..len]).is_none());
+        }
+        let mut header = V2_TCP4.to_vec();
+        header[15] = 0x26;
+        header.extend([PP2_TYPE_NOOP, 0]);
+        assert!(ProxyHeader::parse_v2(&header).is_none());
+
+        let mut header = V2_TCP4.to_vec();
+        header[12] = 0x31;
+        assert!(ProxyHeader::parse_v2(&header).is_none());
+        header[12] = 0x22;
+        assert!(ProxyHeader::parse_v2(&header).is_none());
+        header[12] = 0x21;
+        header[13] = 0x31;
+        assert!(ProxyHeader::parse_v2(&header).is_none());
+        header[13] = 0x41;
+        assert!(ProxyHeader::parse_v2(&header).is_none());
+    }
+
+    #[tokio::test]
+    async fn accept_header_and_replay_the_rest() {
+        let data = [V2_TCP4, b"GET / HTTP/1.1\r\n"].concat();
+        let (mut stream, header) = accept_proxy(&data[..], Some(ProxyProtocol::Required))
+            .await
+            .unwrap();
+        assert!(header.unwrap().proxied);
+        let mut rest = Vec::new();
+        stream.read_to_end(&mut rest).await.unwrap();
+        assert_eq!(rest, b"GET / HTTP/1.1\r\n");
+
+        let data = b"GET / HTTP/1.1\r\n";
+        let (mut stream, header) = accept_proxy(&data[..], Some(ProxyProtocol::Optional))
+            .await
+            .unwrap();
+        assert!(header.is_none());
+        let mut rest = Vec::new();
+        stream.read_to_end(&mut rest).await.unwrap();
+        assert_eq!(rest, data);
+    }
+
+    #[tokio::test]
+    async fn reject_truncated_or_missing_header() {
+        for header in [V1_TCP4, V2_TCP4] {
+            for len in 1..header.len() {
+                let err = accept_proxy(&header[..len], Some(ProxyProtocol::Optional))
+                    .await
+                    .err()
+                    .unwrap();
+                assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+            }
+        }
+
+        let err = accept_proxy(&b"GET / HTTP/1.1\r\n"[..], Some(ProxyProtocol::Required))
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        let header = [b"PROXY TCP4 ".as_slice(), &[b'1'; V1_MAX_LEN]].concat();
+        let err = accept_proxy(&header[..], Some(ProxyProtocol::Optional))
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
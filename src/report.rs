@@ -1,25 +1,93 @@
-use rustls::internal::msgs::handshake::ClientHelloPayload;
 use serde_derive::Serialize;
+use std::net::SocketAddr;
+use std::time::UNIX_EPOCH;
 
-use crate::{akamai::Akamai, http2::Frame, ja3::Ja3, ts1::Ts1Http2};
+use crate::{
+    akamai::Akamai,
+    client_hello::{ClientHelloDiff, ClientHelloReport},
+    connection::Connection,
+    handshake::ClientHello,
+    hpack::HpackFingerprint,
+    http1::{
+        redact, H2cUpgradeSettings, HeaderCasing, Http1Fingerprint, Http1Header, Http1Request,
+        LineEnding,
+    },
+    http2::{
+        ExtensiblePriority, Frame, Header, Http2Frames, Priority, PriorityUpdateFrame,
+        RequestFrames,
+    },
+    ja3::Ja3,
+    ja4::Ja4,
+    negotiated::Negotiated,
+    priorities::Priorities,
+    priority_tree::PriorityTreeReport,
+    proxy::ProxyHeader,
+    tcp::{TcpConnection, TcpInfo, TcpSyn},
+    tls::Fragmentation,
+    ts1::{Ts1Http2, Ts1Tls},
+};
 
 #[derive(Clone, Serialize)]
 pub struct Report {
+    pub connection: ConnectionReport,
     pub tls: Option<TlsReport>,
     pub http2: Option<Http2Report>,
+    pub http1: Option<Http1Report>,
+    pub tcp: Option<TcpReport>,
+    pub proxy: Option<ProxyHeader>,
 }
 
 #[derive(Clone, Serialize)]
 pub struct TlsReport {
     pub ja3: Ja3,
     pub ja3_sort_ext: Ja3,
+    pub ja4: Ja4,
+    pub ja4_o: Ja4,
+    pub ts1: Ts1Tls,
+    pub client_hello: ClientHelloReport,
+    pub fragmentation: Fragmentation,
+    pub hello_retry: Option<HelloRetryReport>,
+    pub negotiated: Negotiated,
 }
 
 impl TlsReport {
-    pub fn new(hello: &ClientHelloPayload) -> Self {
+    pub fn new(
+        hello: &ClientHello,
+        fragmentation: Fragmentation,
+        hello_retry: Option<HelloRetryReport>,
+        negotiated: Negotiated,
+    ) -> Self {
         Self {
             ja3: Ja3::new(hello, false),
             ja3_sort_ext: Ja3::new(hello, true),
+            ja4: Ja4::new(hello, false),
+            ja4_o: Ja4::new(hello, true),
+            ts1: Ts1Tls::new(hello),
+            client_hello: ClientHelloReport::new(hello),
+            fragmentation,
+            hello_retry,
+            negotiated,
+        }
+    }
+}
+
+#[derive(Clone, Serialize)]
+pub struct HelloRetryReport {
+    pub ja3: Ja3,
+    pub ja4: Ja4,
+    pub client_hello: ClientHelloReport,
+    pub fragmentation: Fragmentation,
+    pub diff: ClientHelloDiff,
+}
+
+impl HelloRetryReport {
+    pub fn new(first: &ClientHello, hello: &ClientHello, fragmentation: Fragmentation) -> Self {
+        Self {
+            ja3: Ja3::new(hello, false),
+            ja4: Ja4::new(hello, false),
+            client_hello: ClientHelloReport::new(hello),
+            fragmentation,
+            diff: ClientHelloDiff::new(first, hello),
         }
     }
 }
@@ -27,18 +95,181 @@ impl TlsReport {
 #[derive(Clone, Serialize)]
 pub struct Http2Report {
     pub akamai: Akamai,
+    pub akamai_headers_priority: Akamai,
     pub ts1: Ts1Http2,
+    pub hpack: Option<HpackFingerprint>,
+    pub priorities: Priorities,
+    pub priority_tree: PriorityTreeReport,
+    pub frames: Vec<Frame>,
+    pub stream: Option<Http2StreamReport>,
 }
 
 impl Http2Report {
-    pub fn new(frames: &[Frame]) -> Option<Self> {
-        if frames.is_empty() {
-            None
+    /// Fingerprints the connection preface, along with the frames of the
+    /// request being served and the priority tree built so far.
+    pub fn new(frames: &mut Http2Frames, request: Option<RequestFrames>) -> Option<Self> {
+        let preface = &frames.preface;
+        if preface.is_empty() {
+            return None;
+        }
+        Some(Self {
+            akamai: Akamai::new(preface, false),
+            akamai_headers_priority: Akamai::new(preface, true),
+            ts1: Ts1Http2::new(preface),
+            hpack: HpackFingerprint::new(preface),
+            priorities: Priorities::new(preface, &frames.priority_updates),
+            priority_tree: frames.priority_tree.report().clone(),
+            frames: preface.to_vec(),
+            stream: request
+                .and_then(|request| Http2StreamReport::new(request, &frames.priority_updates)),
+        })
+    }
+}
+
+#[derive(Clone, Serialize)]
+pub struct Http2StreamReport {
+    pub stream_id: u32,
+    pub header_order: Vec<String>,
+    /// The decoded header list in the order it was sent, with the values of
+    /// credential headers redacted as for HTTP/1.
+    pub headers: Vec<Header>,
+    pub header_block_fragments: usize,
+    pub priority: Option<Priority>,
+    /// The priority of the last PRIORITY_UPDATE frame for the stream, or else
+    /// the `priority` header.
+    pub extensible_priority: Option<ExtensiblePriority>,
+    pub preceding_frames: Vec<Frame>,
+}
+
+impl Http2StreamReport {
+    pub fn new(mut request: RequestFrames, updates: &[PriorityUpdateFrame]) -> Option<Self> {
+        let Some(Frame::Headers(headers)) = request.frames.pop() else {
+            return None;
+        };
+        let extensible_priority = updates
+            .iter()
+            .rev()
+            .find(|frame| frame.prioritized_stream_id == request.stream_id)
+            .map(|frame| frame.priority.clone())
+            .or_else(|| {
+                headers
+                    .headers
+                    .iter()
+                    .find(|header| header.name == "priority")
+                    .map(|header| ExtensiblePriority::parse(&header.value))
+            });
+        Some(Self {
+            stream_id: request.stream_id,
+            header_order: headers
+                .headers
+                .iter()
+                .map(|header| header.name.clone())
+                .collect(),
+            headers: headers
+                .headers
+                .into_iter()
+                .map(|header| Header {
+                    value: redact(&header.name, &header.value).unwrap_or(header.value),
+                    ..header
+                })
+                .collect(),
+            header_block_fragments: headers.fragments,
+            priority: headers.priority,
+            extensible_priority,
+            preceding_frames: request.frames,
+        })
+    }
+}
+
+#[derive(Clone, Serialize)]
+pub struct Http1Report {
+    pub fingerprint: Http1Fingerprint,
+    pub request_line: String,
+    pub version: String,
+    pub headers: Vec<Http1Header>,
+    pub header_casing: HeaderCasing,
+    pub line_ending: LineEnding,
+    pub connection: Vec<String>,
+    pub keep_alive: bool,
+    pub h2c_upgrade_settings: Option<H2cUpgradeSettings>,
+    pub raw: String,
+}
+
+impl Http1Report {
+    pub fn new(request: Http1Request) -> Self {
+        Self {
+            fingerprint: Http1Fingerprint::new(&request),
+            header_casing: request.header_casing(),
+            connection: request.connection(),
+            keep_alive: request.keep_alive(),
+            h2c_upgrade_settings: request.h2c_upgrade_settings(),
+            request_line: request.request_line,
+            version: request.version,
+            headers: request.headers,
+            line_ending: request.line_ending,
+            raw: request.raw,
+        }
+    }
+}
+
+#[derive(Clone, Serialize)]
+pub struct TcpReport {
+    pub syn: Option<TcpSyn>,
+    pub info: Option<TcpInfo>,
+}
+
+impl TcpReport {
+    pub fn new(conn: &TcpConnection) -> Self {
+        Self {
+            syn: conn.syn.clone(),
+            info: conn.info(),
+        }
+    }
+}
+
+#[derive(Clone, Serialize)]
+pub struct ConnectionReport {
+    pub id: u64,
+    pub remote_addr: SocketAddr,
+    pub local_addr: Option<SocketAddr>,
+    /// The client's address, as given by the PROXY protocol header if any.
+    pub client_addr: SocketAddr,
+    pub ip_version: u8,
+    /// Milliseconds since the Unix epoch.
+    pub started_at: u64,
+    /// The duration of the TLS handshake, not counting the wait for a PROXY
+    /// protocol header. `None` on cleartext connections.
+    pub handshake_us: Option<u64>,
+    /// The number of requests served on the connection, including this one.
+    pub requests: usize,
+}
+
+impl ConnectionReport {
+    pub fn new(conn: &Connection, requests: usize) -> Self {
+        let client_addr = conn
+            .proxy
+            .as_ref()
+            .and_then(|proxy| proxy.source)
+            .unwrap_or(conn.remote_addr);
+        let ip_version = if client_addr.ip().to_canonical().is_ipv4() {
+            4
         } else {
-            Some(Self {
-                akamai: Akamai::new(frames),
-                ts1: Ts1Http2::new(frames),
-            })
+            6
+        };
+        Self {
+            id: conn.id,
+            remote_addr: conn.remote_addr,
+            local_addr: conn.local_addr,
+            client_addr,
+            ip_version,
+            started_at: conn
+                .started_at
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |time| time.as_millis() as u64),
+            handshake_us: conn
+                .handshake_duration
+                .map(|duration| duration.as_micros() as u64),
+            requests,
         }
     }
 }
@@ -0,0 +1,411 @@
+use serde_derive::Serialize;
+use std::io;
+use tokio::net::{TcpListener, TcpStream};
+
+const TCP_OPTION_EOL: u8 = 0;
+const TCP_OPTION_NOP: u8 = 1;
+const TCP_OPTION_MSS: u8 = 2;
+const TCP_OPTION_WINDOW_SCALE: u8 = 3;
+const TCP_OPTION_SACK_PERMITTED: u8 = 4;
+const TCP_OPTION_SACK: u8 = 5;
+const TCP_OPTION_TIMESTAMP: u8 = 8;
+
+const IP_PROTOCOL_TCP: u8 = 6;
+
+/// Asks the kernel to keep the SYN of every connection accepted on `listener`.
+#[cfg(target_os = "linux")]
+pub fn save_syn(listener: &TcpListener) -> io::Result<()> {
+    use std::os::fd::AsRawFd;
+
+    let enable: libc::c_int = 1;
+    let ret = unsafe {
+        libc::setsockopt(
+            listener.as_raw_fd(),
+            libc::IPPROTO_TCP,
+            libc::TCP_SAVE_SYN,
+            &enable as *const libc::c_int as *const libc::c_void,
+            std::mem::size_of_val(&enable) as libc::socklen_t,
+        )
+    };
+    if ret == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn save_syn(_listener: &TcpListener) -> io::Result<()> {
+    Err(io::ErrorKind::Unsupported.into())
+}
+
+/// Returns the IP and TCP headers of the SYN that opened `stream`. The kernel
+/// hands them out only once.
+#[cfg(target_os = "linux")]
+fn saved_syn(stream: &TcpStream) -> Option<Vec<u8>> {
+    use std::os::fd::AsRawFd;
+
+    let mut buf = vec![0u8; 512];
+    let mut len = buf.len() as libc::socklen_t;
+    let ret = unsafe {
+        libc::getsockopt(
+            stream.as_raw_fd(),
+            libc::IPPROTO_TCP,
+            libc::TCP_SAVED_SYN,
+            buf.as_mut_ptr() as *mut libc::c_void,
+            &mut len,
+        )
+    };
+    if ret != 0 || len == 0 {
+        return None;
+    }
+    buf.truncate(len as usize);
+    Some(buf)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn saved_syn(_stream: &TcpStream) -> Option<Vec<u8>> {
+    None
+}
+
+/// The TCP connection below a stream: the SYN that opened it, and the socket
+/// for reading TCP_INFO after the stream has been handed to other layers.
+pub struct TcpConnection {
+    pub syn: Option<TcpSyn>,
+    /// A duplicate of the stream's descriptor, so that it keeps referring to
+    /// the same socket for as long as this value lives.
+    #[cfg(target_os = "linux")]
+    fd: Option<std::os::fd::OwnedFd>,
+}
+
+impl TcpConnection {
+    pub fn new(stream: &TcpStream) -> Self {
+        Self {
+            syn: saved_syn(stream).as_deref().and_then(TcpSyn::parse),
+            #[cfg(target_os = "linux")]
+            fd: std::os::fd::AsFd::as_fd(stream).try_clone_to_owned().ok(),
+        }
+    }
+
+    /// Reads the current TCP_INFO of the socket.
+    #[cfg(target_os = "linux")]
+    pub fn info(&self) -> Option<TcpInfo> {
+        use std::os::fd::AsRawFd;
+
+        let fd = self.fd.as_ref()?;
+        let mut info = std::mem::MaybeUninit::<libc::tcp_info>::zeroed();
+        let mut len = std::mem::size_of::<libc::tcp_info>() as libc::socklen_t;
+        let ret = unsafe {
+            libc::getsockopt(
+                fd.as_raw_fd(),
+                libc::IPPROTO_TCP,
+                libc::TCP_INFO,
+                info.as_mut_ptr() as *mut libc::c_void,
+                &mut len,
+            )
+        };
+        if ret != 0 {
+            return None;
+        }
+        let info = unsafe { info.assume_init() };
+        // Older kernels fill in a shorter structure.
+        let has = |end: usize| len as usize >= end;
+        let min_rtt_end = std::mem::offset_of!(libc::tcp_info, tcpi_min_rtt) + 4;
+        let snd_wnd_end = std::mem::offset_of!(libc::tcp_info, tcpi_snd_wnd) + 4;
+        Some(TcpInfo {
+            rtt_us: info.tcpi_rtt,
+            rtt_var_us: info.tcpi_rttvar,
+            min_rtt_us: has(min_rtt_end).then_some(info.tcpi_min_rtt),
+            snd_mss: info.tcpi_snd_mss,
+            rcv_mss: info.tcpi_rcv_mss,
+            snd_cwnd: info.tcpi_snd_cwnd,
+            total_retrans: info.tcpi_total_retrans,
+            peer_rcv_wnd: has(snd_wnd_end).then_some(info.tcpi_snd_wnd),
+        })
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub fn info(&self) -> Option<TcpInfo> {
+        None
+    }
+}
+
+/// Connection metrics from TCP_INFO.
+#[derive(Clone, Serialize)]
+pub struct TcpInfo {
+    pub rtt_us: u32,
+    pub rtt_var_us: u32,
+    pub min_rtt_us: Option<u32>,
+    pub snd_mss: u32,
+    pub rcv_mss: u32,
+    pub snd_cwnd: u32,
+    pub total_retrans: u32,
+    /// The receive window last advertised by the peer, in bytes.
+    pub peer_rcv_wnd: Option<u32>,
+}
+
+/// The IP and TCP header fields of a SYN that are set by the client's
+/// operating system.
+#[derive(Clone, Serialize)]
+pub struct TcpSyn {
+    pub ip_version: u8,
+    pub ttl: u8,
+    pub initial_ttl: u8,
+    pub dont_fragment: bool,
+    pub window_size: u16,
+    pub mss: Option<u16>,
+    pub window_scale: Option<u8>,
+    pub options: Vec<u8>,
+    pub quirks: Vec<&'static str>,
+    pub ja4t: String,
+    pub p0f: String,
+}
+
+impl TcpSyn {
+    pub fn parse(packet: &[u8]) -> Option<Self> {
+        let mut quirks = Vec::new();
+        let (ip_version, ttl, dont_fragment, ip_options_len, tcp) = match packet.first()? >> 4 {
+            4 => {
+                let header_len = (packet[0] & 0x0f) as usize * 4;
+                if header_len < 20 || packet.len() < header_len || packet[9] != IP_PROTOCOL_TCP {
+                    return None;
+                }
+                let id = u16::from_be_bytes([packet[4], packet[5]]);
+                let dont_fragment = packet[6] & 0x40 != 0;
+                if dont_fragment && id != 0 {
+                    quirks.push("id+");
+                }
+                if !dont_fragment && id == 0 {
+                    quirks.push("id-");
+                }
+                if packet[1] & 0x03 != 0 {
+                    quirks.push("ecn");
+                }
+                (
+                    4,
+                    packet[8],
+                    dont_fragment,
+                    header_len - 20,
+                    &packet[header_len..],
+                )
+            }
+            6 => {
+                if packet.len() < 40 || packet[6] != IP_PROTOCOL_TCP {
+                    return None;
+                }
+                let flow_label = u32::from_be_bytes([0, packet[1] & 0x0f, packet[2], packet[3]]);
+                if flow_label != 0 {
+                    quirks.push("flow");
+                }
+                if (u16::from_be_bytes([packet[0], packet[1]]) >> 4) & 0x03 != 0 {
+                    quirks.push("ecn");
+                }
+                (6, packet[7], false, 0, &packet[40..])
+            }
+            _ => return None,
+        };
+        if dont_fragment {
+            quirks.insert(0, "df");
+        }
+
+        if tcp.len() < 20 {
+            return None;
+        }
+        let window_size = u16::from_be_bytes([tcp[14], tcp[15]]);
+        let data_offset = (tcp[12] >> 4) as usize * 4;
+        if data_offset < 20 || tcp.len() < data_offset {
+            return None;
+        }
+
+        let mut options = Vec::new();
+        let mut layout = Vec::new();
+        let mut mss = None;
+        let mut window_scale = None;
+        let mut data = &tcp[20..data_offset];
+        while let Some(&kind) = data.first() {
+            options.push(kind);
+            if kind == TCP_OPTION_EOL {
+                layout.push(format!("eol+{}", data.len() - 1));
+                break;
+            }
+            if kind == TCP_OPTION_NOP {
+                layout.push("nop".to_string());
+                data = &data[1..];
+                continue;
+            }
+            let len = *data.get(1)? as usize;
+            if len < 2 || data.len() < len {
+                return None;
+            }
+            let value = &data[2..len];
+            let name = match kind {
+                TCP_OPTION_MSS if value.len() == 2 => {
+                    mss = Some(u16::from_be_bytes([value[0], value[1]]));
+                    "mss".to_string()
+                }
+                TCP_OPTION_WINDOW_SCALE if value.len() == 1 => {
+                    window_scale = Some(value[0]);
+                    "ws".to_string()
+                }
+                TCP_OPTION_SACK_PERMITTED => "sok".to_string(),
+                TCP_OPTION_SACK => "sack".to_string(),
+                TCP_OPTION_TIMESTAMP if value.len() == 8 => {
+                    if value[..4] == [0; 4] {
+                        quirks.push("ts1-");
+                    }
+                    "ts".to_string()
+                }
+                _ => format!("?{kind}"),
+            };
+            layout.push(name);
+            data = &data[len..];
+        }
+
+        let initial_ttl = [32, 64, 128, 255]
+            .into_iter()
+            .find(|&initial| ttl <= initial)
+            .unwrap_or(255);
+
+        let ja4t = format!(
+            "{window_size}_{}_{}_{}",
+            options
+                .iter()
+                .map(|kind| kind.to_string())
+                .collect::<Vec<_>>()
+                .join("-"),
+            mss.unwrap_or_default(),
+            window_scale.unwrap_or_default(),
+        );
+
+        // p0f describes the window as a multiple of the MSS when it is one.
+        let window = match mss {
+            Some(mss) if mss > 0 && window_size % mss == 0 => {
+                format!("mss*{}", window_size / mss)
+            }
+            _ => window_size.to_string(),
+        };
+        let p0f = format!(
+            "{ip_version}:{ttl}+{}:{ip_options_len}:{}:{window},{}:{}:{}:0",
+            initial_ttl - ttl,
+            mss.map_or("*".to_string(), |mss| mss.to_string()),
+            window_scale.unwrap_or_default(),
+            layout.join(","),
+            quirks.join(","),
+        );
+
+        Some(Self {
+            ip_version,
+            ttl,
+            initial_ttl,
+            dont_fragment,
+            window_size,
+            mss,
+            window_scale,
+            options,
+            quirks,
+            ja4t,
+            p0f,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// SYNs sent by Linux 6.18 over the loopback interface.
+    const SYN_V4: &str = "4500003c16ba4000400626007f0000017f000001e122dc93a3ac099500000000\
+                          a002ffd7fe3000000204ffd70402080abebb32c5000000000103030a";
+    const SYN_V6: &str = "60072e08002806400000000000000000000000000000000100000000000000000\
+                          000000000000001bd24c18b5cbd3a7f00000000a002ffc4003000000204ffc404\
+                          02080a1914fcca000000000103030a";
+
+    fn packet(hex: &str) -> Vec<u8> {
+        hex::decode(hex).unwrap()
+    }
+
+    #[test]
+    fn parse_ipv4_syn() {
+        let syn = TcpSyn::parse(&packet(SYN_V4)).unwrap();
+        assert_eq!(syn.ip_version, 4);
+        assert_eq!(syn.ttl, 64);
+        assert_eq!(syn.initial_ttl, 64);
+        assert!(syn.dont_fragment);
+        assert_eq!(syn.mss, Some(65495));
+        assert_eq!(syn.window_scale, Some(10));
+        assert_eq!(syn.ja4t, "65495_2-4-8-1-3_65495_10");
+        assert_eq!(
+            syn.p0f,
+            "4:64+0:0:65495:mss*1,10:mss,sok,ts,nop,ws:df,id+:0"
+        );
+    }
+
+    #[test]
+    fn parse_ipv6_syn() {
+        let syn = TcpSyn::parse(&packet(SYN_V6)).unwrap();
+        assert_eq!(syn.ip_version, 6);
+        assert_eq!(syn.ttl, 64);
+        assert!(!syn.dont_fragment);
+        assert_eq!(syn.ja4t, "65476_2-4-8-1-3_65476_10");
+        assert_eq!(syn.p0f, "6:64+0:0:65476:mss*1,10:mss,sok,ts,nop,ws:flow:0");
+    }
+
+    #[test]
+    fn reject_truncated_syn() {
+        for hex in [SYN_V4, SYN_V6] {
+            let packet = packet(hex);
+            for len in 0..packet.len() {
+                assert!(
+                    TcpSyn::parse(&packet[..len]).is_none(),
+                    "parsed {len} bytes"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn reject_malformed_syn() {
+        let syn = packet(SYN_V4);
+
+        // Not TCP.
+        let mut packet = syn.clone();
+        packet[9] = 17;
+        assert!(TcpSyn::parse(&packet).is_none());
+
+        // An IP header length below the minimum.
+        let mut packet = syn.clone();
+        packet[0] = 0x44;
+        assert!(TcpSyn::parse(&packet).is_none());
+
+        // A TCP data offset below the minimum, or past the end of the packet.
+        let mut packet = syn.clone();
+        packet[32] = 0x40;
+        assert!(TcpSyn::parse(&packet).is_none());
+        packet[32] = 0xf0;
+        assert!(TcpSyn::parse(&packet).is_none());
+
+        // An option shorter than its own header, or running past the end.
+        let mut packet = syn.clone();
+        packet[41] = 1;
+        assert!(TcpSyn::parse(&packet).is_none());
+        packet[41] = 40;
+        assert!(TcpSyn::parse(&packet).is_none());
+
+        // Neither IPv4 nor IPv6.
+        let mut packet = syn;
+        packet[0] = 0x55;
+        assert!(TcpSyn::parse(&packet).is_none());
+    }
+
+    #[test]
+    fn corrupted_options_do_not_panic() {
+        let syn = packet(SYN_V4);
+        for offset in 40..syn.len() {
+            for value in [0, 1, 2, 3, 4, 8, 0xff] {
+                let mut packet = syn.clone();
+                packet[offset] = value;
+                let _ = TcpSyn::parse(&packet);
+            }
+        }
+    }
+}
@@ -1,40 +1,149 @@
-use rustls::internal::msgs::codec::Reader;
-use rustls::internal::msgs::handshake::{
-    ClientHelloPayload, HandshakeMessagePayload, HandshakePayload,
+use crate::handshake::{
+    ClientHello, HandshakeMessage, Record, ServerHello, ServerKeyExchange,
+    CONTENT_TYPE_CHANGE_CIPHER_SPEC, CONTENT_TYPE_HANDSHAKE, HANDSHAKE_CLIENT_HELLO,
+    HANDSHAKE_SERVER_HELLO, HANDSHAKE_SERVER_HELLO_DONE, HANDSHAKE_SERVER_KEY_EXCHANGE,
+    RECORD_HEADER_LEN,
 };
-use rustls::internal::msgs::message::{Message, MessagePayload, OpaqueMessage};
+use crate::inspect::Inspect;
+use serde_derive::Serialize;
 use std::io::IoSlice;
 use std::pin::Pin;
 use std::task;
 use std::task::Poll;
-use tokio::io::{self, AsyncRead, AsyncWrite, ReadBuf};
-use tokio::net::TcpStream;
+use tokio::io::{self, AsyncRead, AsyncReadExt, AsyncWrite, ReadBuf};
+
+/// Limits on the client data buffered while waiting for a ClientHello. A
+/// ClientHello fits in 5 records of at most 2^14 bytes, so a client needing
+/// more is not sending one.
+const MAX_HELLO_RECORDS: usize = 8;
+const MAX_BUFFERED: usize = MAX_HELLO_RECORDS * (RECORD_HEADER_LEN + (1 << 14));
 
 pin_project_lite::pin_project! {
-    pub struct TlsInspctor {
+    pub struct TlsInspctor<S> {
         #[pin]
-        inner: TcpStream,
+        inner: S,
 
         buf: Vec<u8>,
-        client_hello: Option<ClientHelloPayload>,
+        // Length and number of the complete records at the head of `buf`.
+        scanned: usize,
+        records: usize,
+        reads: usize,
+        done: bool,
+
+        server_buf: Vec<u8>,
+        server_done: bool,
+
+        capture: TlsCapture,
     }
 }
 
-impl TlsInspctor {
-    pub fn new(inner: TcpStream) -> TlsInspctor {
+/// The plaintext handshake messages seen by a `TlsInspctor`.
+#[derive(Default)]
+pub struct TlsCapture {
+    client_hellos: Vec<CapturedHello>,
+    server_hello: Option<HandshakeMessage>,
+    server_key_exchange: Option<HandshakeMessage>,
+}
+
+struct CapturedHello {
+    message: HandshakeMessage,
+    reads: usize,
+}
+
+#[derive(Clone, Serialize)]
+pub struct Fragmentation {
+    pub records: usize,
+    pub reads: usize,
+}
+
+impl<S> TlsInspctor<S> {
+    pub fn new(inner: S) -> Self {
         Self {
             inner,
             buf: Vec::new(),
-            client_hello: None,
+            scanned: 0,
+            records: 0,
+            reads: 0,
+            done: false,
+            server_buf: Vec::new(),
+            server_done: false,
+            capture: TlsCapture::default(),
         }
     }
+}
+
+impl<S: AsyncRead + Unpin> TlsInspctor<S> {
+    /// Reads until the first ClientHello has been captured, or until none can
+    /// be, so that the TLS server can be configured for it. Returns the data
+    /// read, which has to be replayed to the server.
+    pub async fn read_client_hello(&mut self) -> io::Result<Vec<u8>> {
+        let mut buf = Vec::new();
+        let mut chunk = vec![0; RECORD_HEADER_LEN + (1 << 14)];
+        while self.capture.client_hellos.is_empty() && !self.done {
+            let len = self.read(&mut chunk).await?;
+            if len == 0 {
+                break;
+            }
+            buf.extend(&chunk[..len]);
+        }
+        Ok(buf)
+    }
+}
+
+impl<S> Inspect for TlsInspctor<S> {
+    type Inner = S;
+    type Capture = TlsCapture;
+
+    fn get_ref(&self) -> &S {
+        &self.inner
+    }
+
+    fn capture(&self) -> &TlsCapture {
+        &self.capture
+    }
+}
+
+impl TlsCapture {
+    /// Returns the initial ClientHello and how many TLS records and TCP reads
+    /// it spanned.
+    pub fn client_hello(&self) -> Option<(ClientHello<'_>, Fragmentation)> {
+        self.client_hellos.first().and_then(CapturedHello::parse)
+    }
+
+    /// Returns the ClientHello sent in response to a HelloRetryRequest.
+    pub fn retried_client_hello(&self) -> Option<(ClientHello<'_>, Fragmentation)> {
+        self.client_hellos.get(1).and_then(CapturedHello::parse)
+    }
+
+    /// Returns the ServerHello that concluded the negotiation, skipping any
+    /// HelloRetryRequest.
+    pub fn server_hello(&self) -> Option<ServerHello<'_>> {
+        self.server_hello
+            .as_ref()
+            .and_then(|msg| ServerHello::parse(&msg.body))
+    }
+
+    /// Returns the ServerKeyExchange sent during a TLS 1.2 full handshake.
+    pub fn server_key_exchange(&self) -> Option<ServerKeyExchange> {
+        self.server_key_exchange
+            .as_ref()
+            .and_then(|msg| ServerKeyExchange::parse(&msg.body))
+    }
+}
 
-    pub fn client_hello(&self) -> Option<&ClientHelloPayload> {
-        self.client_hello.as_ref()
+impl CapturedHello {
+    fn parse(&self) -> Option<(ClientHello<'_>, Fragmentation)> {
+        let msg = &self.message;
+        let hello = ClientHello::parse(msg.record_version, &msg.body)?;
+        let fragmentation = Fragmentation {
+            records: msg.records,
+            reads: self.reads,
+        };
+        Some((hello, fragmentation))
     }
 }
 
-impl AsyncRead for TlsInspctor {
+impl<S: AsyncRead> AsyncRead for TlsInspctor<S> {
     #[inline]
     fn poll_read(
         self: Pin<&mut Self>,
@@ -45,23 +154,157 @@ impl AsyncRead for TlsInspctor {
         let me = self.project();
         let poll = me.inner.poll_read(cx, buf);
 
-        if me.client_hello.is_none() {
+        if !*me.done && buf.filled().len() > len {
             me.buf.extend(&buf.filled()[len..]);
-            *me.client_hello = parse_client_hello(me.buf);
+            *me.reads += 1;
+            *me.done = scan_client_records(
+                me.buf,
+                me.scanned,
+                me.records,
+                me.reads,
+                &mut me.capture.client_hellos,
+            );
+            if *me.done {
+                *me.buf = Vec::new();
+            }
         }
 
         poll
     }
 }
 
-impl AsyncWrite for TlsInspctor {
+/// Looks for ClientHellos once the data read so far completes another record,
+/// resuming the scan where the previous read left off. Returns `true` once no
+/// more ClientHellos can follow or the client went over the buffer limits.
+fn scan_client_records(
+    buf: &mut Vec<u8>,
+    scanned: &mut usize,
+    records: &mut usize,
+    reads: &mut usize,
+    client_hellos: &mut Vec<CapturedHello>,
+) -> bool {
+    let mut complete = false;
+    while let Some((_, len)) = Record::parse(&buf[*scanned..]) {
+        *scanned += len;
+        *records += 1;
+        complete = true;
+    }
+    if complete {
+        let len = buf.len();
+        if capture_client_hellos(buf, reads, client_hellos) {
+            return true;
+        }
+        if buf.len() < len {
+            *scanned -= len - buf.len();
+            *records = 0;
+            let mut offset = 0;
+            while let Some((_, len)) = Record::parse(&buf[offset..*scanned]) {
+                offset += len;
+                *records += 1;
+            }
+        }
+    }
+    *records > MAX_HELLO_RECORDS || buf.len() > MAX_BUFFERED
+}
+
+/// Consumes the ClientHellos at the head of `buf`. Returns `true` once no more
+/// ClientHellos can follow, i.e. after a retried ClientHello or as soon as the
+/// client sends anything other than a ChangeCipherSpec or a ClientHello.
+fn capture_client_hellos(
+    buf: &mut Vec<u8>,
+    reads: &mut usize,
+    client_hellos: &mut Vec<CapturedHello>,
+) -> bool {
+    loop {
+        let (content_type, record_len) = match Record::parse(buf) {
+            Some((record, len)) => (record.content_type, len),
+            None => return false,
+        };
+        if content_type == CONTENT_TYPE_CHANGE_CIPHER_SPEC && !client_hellos.is_empty() {
+            buf.drain(..record_len);
+            continue;
+        }
+        if content_type != CONTENT_TYPE_HANDSHAKE {
+            return true;
+        }
+        match HandshakeMessage::reassemble(buf) {
+            Some(message) if message.msg_type == HANDSHAKE_CLIENT_HELLO => {
+                buf.drain(..message.len);
+                // The read that completed this message may already carry the next one.
+                let next_reads = usize::from(!buf.is_empty());
+                client_hellos.push(CapturedHello {
+                    message,
+                    reads: std::mem::replace(reads, next_reads),
+                });
+                if client_hellos.len() == 2 {
+                    return true;
+                }
+            }
+            Some(_) => return true,
+            None => return false,
+        }
+    }
+}
+
+/// Consumes the server's plaintext handshake messages at the head of `buf`.
+/// Returns `true` once the rest of the flight is encrypted or the server has
+/// nothing more to say before the client's reply.
+fn capture_server_flight(buf: &mut Vec<u8>, capture: &mut TlsCapture) -> bool {
+    loop {
+        let (content_type, record_len) = match Record::parse(buf) {
+            Some((record, len)) => (record.content_type, len),
+            None => return false,
+        };
+        // A HelloRetryRequest may be followed by a ChangeCipherSpec before the
+        // actual ServerHello.
+        if content_type == CONTENT_TYPE_CHANGE_CIPHER_SPEC && capture.server_hello.is_none() {
+            buf.drain(..record_len);
+            continue;
+        }
+        if content_type != CONTENT_TYPE_HANDSHAKE {
+            return true;
+        }
+        let message = match HandshakeMessage::reassemble(buf) {
+            Some(message) => message,
+            None => return false,
+        };
+        buf.drain(..message.len);
+        match message.msg_type {
+            HANDSHAKE_SERVER_HELLO => {
+                let is_retry = ServerHello::parse(&message.body)
+                    .is_some_and(|hello| hello.is_hello_retry_request());
+                if !is_retry {
+                    capture.server_hello = Some(message);
+                }
+            }
+            HANDSHAKE_SERVER_KEY_EXCHANGE => capture.server_key_exchange = Some(message),
+            HANDSHAKE_SERVER_HELLO_DONE => return true,
+            _ => {}
+        }
+    }
+}
+
+impl<S: AsyncWrite> AsyncWrite for TlsInspctor<S> {
     #[inline]
     fn poll_write(
         self: Pin<&mut Self>,
         cx: &mut task::Context<'_>,
         buf: &[u8],
     ) -> Poll<io::Result<usize>> {
-        self.project().inner.poll_write(cx, buf)
+        let me = self.project();
+        let poll = me.inner.poll_write(cx, buf);
+
+        if let Poll::Ready(Ok(written)) = &poll {
+            if !*me.server_done && *written > 0 {
+                me.server_buf.extend(&buf[..*written]);
+                *me.server_done = capture_server_flight(me.server_buf, me.capture);
+                if *me.server_done {
+                    *me.server_buf = Vec::new();
+                }
+            }
+        }
+
+        poll
     }
 
     #[inline]
@@ -70,12 +313,30 @@ impl AsyncWrite for TlsInspctor {
         cx: &mut task::Context<'_>,
         bufs: &[IoSlice<'_>],
     ) -> Poll<io::Result<usize>> {
-        self.project().inner.poll_write_vectored(cx, bufs)
+        let me = self.project();
+        let poll = me.inner.poll_write_vectored(cx, bufs);
+
+        if let Poll::Ready(Ok(written)) = &poll {
+            if !*me.server_done && *written > 0 {
+                let mut remaining = *written;
+                for buf in bufs {
+                    let len = buf.len().min(remaining);
+                    me.server_buf.extend(&buf[..len]);
+                    remaining -= len;
+                }
+                *me.server_done = capture_server_flight(me.server_buf, me.capture);
+                if *me.server_done {
+                    *me.server_buf = Vec::new();
+                }
+            }
+        }
+
+        poll
     }
 
     #[inline]
-    fn poll_flush(self: Pin<&mut Self>, _cx: &mut task::Context<'_>) -> Poll<io::Result<()>> {
-        Poll::Ready(Ok(()))
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<io::Result<()>> {
+        self.project().inner.poll_flush(cx)
     }
 
     #[inline]
@@ -89,21 +350,85 @@ impl AsyncWrite for TlsInspctor {
     }
 }
 
-fn parse_client_hello(data: &[u8]) -> Option<ClientHelloPayload> {
-    let mut reader = Reader::init(data);
-    let msg = OpaqueMessage::read(&mut reader).ok()?;
-    let msg = TryInto::<Message>::try_into(msg.into_plain_message()).ok()?;
-    if let MessagePayload::Handshake {
-        parsed:
-            HandshakeMessagePayload {
-                payload: HandshakePayload::ClientHello(payload),
-                ..
-            },
-        ..
-    } = msg.payload
-    {
-        Some(payload)
-    } else {
-        None
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CURL_CLIENT_HELLO: &[u8] = include_bytes!("../tests/data/curl_client_hello.bin");
+
+    /// Feeds `chunks` to the ClientHello scanner as separate reads, returning
+    /// whether it is done and what it captured.
+    fn scan<'a>(chunks: impl IntoIterator<Item = &'a [u8]>) -> (bool, Vec<CapturedHello>, usize) {
+        let (mut buf, mut scanned, mut records, mut reads) = (Vec::new(), 0, 0, 0);
+        let mut client_hellos = Vec::new();
+        for chunk in chunks {
+            buf.extend(chunk);
+            reads += 1;
+            if scan_client_records(
+                &mut buf,
+                &mut scanned,
+                &mut records,
+                &mut reads,
+                &mut client_hellos,
+            ) {
+                return (true, client_hellos, buf.len());
+            }
+        }
+        (false, client_hellos, buf.len())
+    }
+
+    fn handshake_record(fragment: &[u8]) -> Vec<u8> {
+        let mut record = vec![CONTENT_TYPE_HANDSHAKE, 0x03, 0x01];
+        record.extend((fragment.len() as u16).to_be_bytes());
+        record.extend(fragment);
+        record
+    }
+
+    #[test]
+    fn capture_client_hello_over_reads_and_records() {
+        let (done, hellos, _) = scan(CURL_CLIENT_HELLO.chunks(7));
+        assert!(!done);
+        assert_eq!(hellos.len(), 1);
+        assert_eq!(hellos[0].reads, CURL_CLIENT_HELLO.len().div_ceil(7));
+
+        let records = CURL_CLIENT_HELLO[RECORD_HEADER_LEN..]
+            .chunks(100)
+            .flat_map(handshake_record)
+            .collect::<Vec<_>>();
+        let (done, hellos, len) = scan(records.chunks(64));
+        assert!(!done);
+        assert_eq!(hellos[0].message.records, 6);
+        assert_eq!(len, 0);
+    }
+
+    #[test]
+    fn give_up_on_too_many_records() {
+        // A ClientHello header announcing 16 MiB, followed by one-byte records.
+        let mut data = handshake_record(&[HANDSHAKE_CLIENT_HELLO, 0xff, 0xff, 0xff]);
+        for _ in 0..MAX_HELLO_RECORDS {
+            data.extend(handshake_record(&[0]));
+        }
+        let records = data.chunks(RECORD_HEADER_LEN + 1).count();
+        let (done, hellos, _) = scan(data.chunks(RECORD_HEADER_LEN + 1).take(records - 1));
+        assert!(!done);
+        assert!(hellos.is_empty());
+        let (done, _, _) = scan(data.chunks(RECORD_HEADER_LEN + 1));
+        assert!(done);
+    }
+
+    #[test]
+    fn give_up_on_too_much_data() {
+        // Records longer than TLS allows, carrying a message that never ends.
+        let mut fragment = vec![0; u16::MAX as usize];
+        fragment[..4].copy_from_slice(&[HANDSHAKE_CLIENT_HELLO, 0xff, 0xff, 0xff]);
+        let data = [handshake_record(&fragment), handshake_record(&fragment)].concat();
+        let (done, _, len) = scan(data.chunks(1 << 12));
+        assert!(!done);
+        assert_eq!(len, data.len());
+
+        let data = [data, vec![CONTENT_TYPE_HANDSHAKE; 1 << 12]].concat();
+        let (done, hellos, _) = scan(data.chunks(1 << 12));
+        assert!(done);
+        assert!(hellos.is_empty());
     }
 }
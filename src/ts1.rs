@@ -1,8 +1,174 @@
-use crate::http2::Frame;
+use crate::{
+    handshake::{
+        ClientHello, Extension, ExtensionPayload, EXT_PADDING, EXT_PRE_SHARED_KEY, EXT_SERVER_NAME,
+    },
+    http2::{Frame, Priority, Setting},
+    ja3::is_not_grease,
+};
 use serde_derive::Serialize;
-use serde_json::Value;
+use serde_json::{json, Map, Value};
 use std::collections::BTreeMap;
 
+#[derive(Clone, Serialize)]
+pub struct Ts1Tls {
+    pub sha1: String,
+    pub text: String,
+}
+
+impl Ts1Tls {
+    pub fn new(hello: &ClientHello) -> Self {
+        let ciphers = hello
+            .cipher_suites()
+            .map(grease_or_number)
+            .collect::<Vec<_>>();
+        let extensions = hello
+            .extensions()
+            .map(extension_signature)
+            .collect::<Vec<_>>();
+        let value = json!({
+            "record_version": version_name(hello.record_version),
+            "handshake_version": version_name(hello.legacy_version),
+            "session_id_length": hello.session_id.len(),
+            "ciphersuites": ciphers,
+            "comp_methods": hello.compression_methods,
+            "extensions": extensions,
+        });
+        let text = canonical_json(value);
+        Self {
+            sha1: sha1_smol::Sha1::from(&text).hexdigest(),
+            text,
+        }
+    }
+}
+
+fn extension_signature(ext: Extension) -> Value {
+    let mut map = Map::new();
+    map.insert("type".into(), extension_name(ext.ty));
+
+    // The hostname, padding and PSK identities vary between connections.
+    if matches!(ext.ty, EXT_SERVER_NAME | EXT_PADDING | EXT_PRE_SHARED_KEY) {
+        return Value::Object(map);
+    }
+    map.insert("length".into(), ext.data.len().into());
+
+    match ext.payload() {
+        ExtensionPayload::SupportedGroups(groups) => {
+            let groups = groups.into_iter().map(grease_or_number).collect::<Vec<_>>();
+            map.insert("supported_groups".into(), groups.into());
+        }
+        ExtensionPayload::EcPointFormats(formats) => {
+            map.insert("ec_point_formats".into(), formats.into());
+        }
+        ExtensionPayload::SignatureAlgorithms(schemes) => {
+            let schemes = schemes
+                .into_iter()
+                .map(grease_or_number)
+                .collect::<Vec<_>>();
+            map.insert("sig_hash_algs".into(), schemes.into());
+        }
+        ExtensionPayload::Alpn(protocols) => {
+            let protocols = protocols
+                .into_iter()
+                .map(|protocol| String::from_utf8_lossy(protocol).into_owned())
+                .collect::<Vec<_>>();
+            map.insert("alpn_list".into(), protocols.into());
+        }
+        ExtensionPayload::SupportedVersions(versions) => {
+            let versions = versions.into_iter().map(version_name).collect::<Vec<_>>();
+            map.insert("supported_versions".into(), versions.into());
+        }
+        ExtensionPayload::KeyShare(entries) => {
+            let entries = entries
+                .into_iter()
+                .map(|entry| {
+                    json!({
+                        "group": grease_or_number(entry.group),
+                        "length": entry.key_exchange.len(),
+                    })
+                })
+                .collect::<Vec<_>>();
+            map.insert("key_shares".into(), entries.into());
+        }
+        ExtensionPayload::PskKeyExchangeModes(modes) => {
+            map.insert("psk_ke_modes".into(), modes.into());
+        }
+        ExtensionPayload::StatusRequest { status_type, .. } => {
+            map.insert("status_request_type".into(), status_type.into());
+        }
+        ExtensionPayload::CompressCertificate(algorithms) => {
+            map.insert("algorithms".into(), algorithms.into());
+        }
+        ExtensionPayload::RecordSizeLimit(limit) => {
+            map.insert("record_size_limit".into(), limit.into());
+        }
+        ExtensionPayload::DelegatedCredential(schemes) => {
+            map.insert("sig_hash_algs".into(), schemes.into());
+        }
+        ExtensionPayload::ApplicationSettings(protocols) => {
+            let protocols = protocols
+                .into_iter()
+                .map(|protocol| String::from_utf8_lossy(protocol).into_owned())
+                .collect::<Vec<_>>();
+            map.insert("alps_alpn_list".into(), protocols.into());
+        }
+        ExtensionPayload::Unknown(data) if !data.is_empty() => {
+            map.insert("data".into(), hex::encode(data).into());
+        }
+        _ => (),
+    }
+    Value::Object(map)
+}
+
+fn extension_name(ty: u16) -> Value {
+    let name = match ty {
+        0x0000 => "server_name",
+        0x0005 => "status_request",
+        0x000a => "supported_groups",
+        0x000b => "ec_point_formats",
+        0x000d => "signature_algorithms",
+        0x0010 => "application_layer_protocol_negotiation",
+        0x0012 => "signed_certificate_timestamp",
+        0x0015 => "padding",
+        0x0016 => "encrypt_then_mac",
+        0x0017 => "extended_master_secret",
+        0x001b => "compress_certificate",
+        0x001c => "record_size_limit",
+        0x0022 => "delegated_credentials",
+        0x0023 => "session_ticket",
+        0x0029 => "pre_shared_key",
+        0x002a => "early_data",
+        0x002b => "supported_versions",
+        0x002d => "psk_key_exchange_modes",
+        0x0031 => "post_handshake_auth",
+        0x0033 => "keyshare",
+        0x4469 => "application_settings",
+        0xfe0d => "encrypted_client_hello",
+        0xff01 => "renegotiation_info",
+        _ => return grease_or_number(ty),
+    };
+    name.into()
+}
+
+fn version_name(version: u16) -> Value {
+    let name = match version {
+        0x0300 => "SSL_VERSION_3_0",
+        0x0301 => "TLS_VERSION_1_0",
+        0x0302 => "TLS_VERSION_1_1",
+        0x0303 => "TLS_VERSION_1_2",
+        0x0304 => "TLS_VERSION_1_3",
+        _ => return grease_or_number(version),
+    };
+    name.into()
+}
+
+fn grease_or_number(value: u16) -> Value {
+    if is_not_grease(&value) {
+        value.into()
+    } else {
+        "GREASE".into()
+    }
+}
+
 #[derive(Clone, Serialize)]
 pub struct Ts1Http2 {
     pub sha1: String,
@@ -11,14 +177,9 @@ pub struct Ts1Http2 {
 
 impl Ts1Http2 {
     pub fn new(frames: &[Frame]) -> Self {
-        let frames = frames
-            .iter()
-            .map(|frame| match frame {
-                Frame::Unknown(ty) => FrameSignature::Unnamed { frame_type: *ty },
-                _ => FrameSignature::Named(frame),
-            })
-            .collect::<Vec<_>>();
-        let value = serde_json::to_value(frames).unwrap_or_default();
+        let frames = frames.iter().map(FrameSignature::new).collect::<Vec<_>>();
+        let mut value = serde_json::to_value(frames).unwrap_or_default();
+        strip_setting_names(&mut value);
         let text = canonical_json(value);
         Self {
             sha1: sha1_smol::Sha1::from(&text).hexdigest(),
@@ -27,11 +188,112 @@ impl Ts1Http2 {
     }
 }
 
+/// The fields of a frame that a client sends the same way on every
+/// connection. Opaque data, lengths, error codes and debug data are left out.
+#[derive(Default, Serialize)]
+struct FrameSignature<'a> {
+    frame_type: FrameType,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stream_id: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ack: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    end_stream: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    end_headers: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pseudo_headers: Option<&'a [String]>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    priority: Option<&'a Priority>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    settings: Option<&'a [Setting]>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    window_size_increment: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    priority_field_value: Option<&'a str>,
+}
+
 #[derive(Serialize)]
 #[serde(untagged)]
-enum FrameSignature<'a> {
-    Named(&'a Frame),
-    Unnamed { frame_type: u8 },
+enum FrameType {
+    Named(&'static str),
+    Unnamed(u8),
+}
+
+impl Default for FrameType {
+    fn default() -> Self {
+        Self::Unnamed(0)
+    }
+}
+
+impl<'a> FrameSignature<'a> {
+    fn new(frame: &'a Frame) -> Self {
+        let named = |name, stream_id| Self {
+            frame_type: FrameType::Named(name),
+            stream_id: Some(stream_id),
+            ..Default::default()
+        };
+        match frame {
+            Frame::Data(frame) => Self {
+                end_stream: Some(frame.end_stream),
+                ..named("DATA", frame.stream_id)
+            },
+            Frame::Headers(frame) => Self {
+                pseudo_headers: Some(&frame.pseudo_headers),
+                priority: frame.priority.as_ref(),
+                ..named("HEADERS", frame.stream_id)
+            },
+            Frame::Priority(frame) => Self {
+                priority: Some(&frame.priority),
+                ..named("PRIORITY", frame.stream_id)
+            },
+            Frame::RstStream(frame) => named("RST_STREAM", frame.stream_id),
+            Frame::Settings(frame) => Self {
+                settings: Some(&frame.settings),
+                ..named("SETTINGS", frame.stream_id)
+            },
+            Frame::PushPromise(frame) => Self {
+                end_headers: Some(frame.end_headers),
+                ..named("PUSH_PROMISE", frame.stream_id)
+            },
+            Frame::Ping(frame) => Self {
+                ack: Some(frame.ack),
+                ..named("PING", frame.stream_id)
+            },
+            Frame::GoAway(frame) => named("GOAWAY", frame.stream_id),
+            Frame::WindowUpdate(frame) => Self {
+                window_size_increment: Some(frame.window_size_increment),
+                ..named("WINDOW_UPDATE", frame.stream_id)
+            },
+            Frame::Continuation(frame) => Self {
+                end_headers: Some(frame.end_headers),
+                ..named("CONTINUATION", frame.stream_id)
+            },
+            Frame::Altsvc(frame) => named("ALTSVC", frame.stream_id),
+            Frame::Origin(frame) => named("ORIGIN", frame.stream_id),
+            Frame::PriorityUpdate(frame) => Self {
+                priority_field_value: Some(&frame.priority_field_value),
+                ..named("PRIORITY_UPDATE", frame.stream_id)
+            },
+            Frame::Unknown(frame) => Self {
+                frame_type: FrameType::Unnamed(frame.ty),
+                ..Default::default()
+            },
+        }
+    }
+}
+
+/// TS1 lists settings by id only, without the names shown in the frame log.
+fn strip_setting_names(frames: &mut Value) {
+    let frames = frames.as_array_mut().into_iter().flatten();
+    for frame in frames {
+        let settings = frame.get_mut("settings").and_then(Value::as_array_mut);
+        for setting in settings.into_iter().flatten() {
+            if let Some(setting) = setting.as_object_mut() {
+                setting.remove("name");
+            }
+        }
+    }
 }
 
 fn canonical_json(value: Value) -> String {
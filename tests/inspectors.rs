@@ -0,0 +1,177 @@
+use pawprint::akamai::Akamai;
+use pawprint::handshake::ExtensionPayload;
+use pawprint::http2::Frame;
+use pawprint::{Http1Inspector, Http2Inspector, Inspect, TlsInspctor};
+use std::sync::Arc;
+use tokio::io::{duplex, AsyncReadExt, AsyncWriteExt};
+use tokio_rustls::rustls;
+
+/// Writes `data` to one end of a pipe small enough to split it over several
+/// reads, and reads it back through `wrap` on the other end.
+async fn read_through<I, F>(data: &[u8], wrap: F) -> I
+where
+    I: tokio::io::AsyncRead + Unpin,
+    F: FnOnce(tokio::io::DuplexStream) -> I,
+{
+    let (mut client, server) = duplex(64);
+    let mut inspector = wrap(server);
+    let written = data.to_vec();
+    let writer = tokio::spawn(async move { client.write_all(&written).await });
+
+    let mut received = vec![0; data.len()];
+    inspector.read_exact(&mut received).await.unwrap();
+    writer.await.unwrap().unwrap();
+    assert_eq!(received, data, "inspectors pass data through unchanged");
+    inspector
+}
+
+fn client_hello() -> Vec<u8> {
+    let mut config = rustls::ClientConfig::builder()
+        .with_safe_defaults()
+        .with_root_certificates(rustls::RootCertStore::empty())
+        .with_no_client_auth();
+    config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+    let mut conn =
+        rustls::ClientConnection::new(Arc::new(config), "localhost".try_into().unwrap()).unwrap();
+    let mut data = Vec::new();
+    while conn.wants_write() {
+        conn.write_tls(&mut data).unwrap();
+    }
+    data
+}
+
+#[tokio::test]
+async fn tls_inspector_captures_client_hello() {
+    let data = client_hello();
+    let inspector = read_through(&data, TlsInspctor::new).await;
+
+    let (hello, fragmentation) = inspector.capture().client_hello().unwrap();
+    assert_eq!(fragmentation.records, 1);
+    assert!(fragmentation.reads > 1);
+    assert!(hello.cipher_suites().count() > 0);
+
+    let alpn = hello.extensions().find_map(|ext| match ext.payload() {
+        ExtensionPayload::Alpn(protocols) => Some(protocols),
+        _ => None,
+    });
+    assert_eq!(alpn, Some(vec![&b"h2"[..], &b"http/1.1"[..]]));
+    let server_name = hello.extensions().find_map(|ext| match ext.payload() {
+        ExtensionPayload::ServerName(names) => names.first().map(|name| name.name),
+        _ => None,
+    });
+    assert_eq!(server_name, Some(&b"localhost"[..]));
+}
+
+fn frame(ty: u8, flags: u8, stream_id: u32, payload: &[u8]) -> Vec<u8> {
+    let mut frame = (payload.len() as u32).to_be_bytes()[1..].to_vec();
+    frame.extend([ty, flags]);
+    frame.extend(stream_id.to_be_bytes());
+    frame.extend(payload);
+    frame
+}
+
+#[tokio::test]
+async fn http2_inspector_captures_preface() {
+    let mut data = b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n".to_vec();
+    // SETTINGS_HEADER_TABLE_SIZE = 65536, SETTINGS_INITIAL_WINDOW_SIZE = 6291456
+    data.extend(frame(0x4, 0, 0, &[0, 1, 0, 1, 0, 0, 0, 4, 0, 0x60, 0, 0]));
+    data.extend(frame(0x8, 0, 0, &15663105u32.to_be_bytes()));
+    // :method GET, :scheme https, :path /, :authority localhost
+    let mut block = vec![0x82, 0x87, 0x84, 0x41, 9];
+    block.extend(b"localhost");
+    data.extend(frame(0x1, 0x5, 1, &block));
+
+    let inspector = read_through(&data, Http2Inspector::new).await;
+
+    let frames = inspector.capture().lock().unwrap();
+    assert_eq!(frames.preface.len(), 3);
+    assert_eq!(
+        Akamai::new(&frames.preface, false).text,
+        "1:65536;4:6291456|15663105|0|m,s,p,a"
+    );
+}
+
+#[tokio::test]
+async fn http1_inspector_captures_request_heads() {
+    let first = "GET / HTTP/1.1\r\nHost: localhost\r\nUser-Agent: test\r\n\r\n";
+    let second = "POST /form HTTP/1.1\nhost: localhost\ncontent-length: 5\n\nhello";
+    let data = format!("{first}{second}");
+
+    let inspector = read_through(data.as_bytes(), Http1Inspector::new).await;
+
+    let requests = inspector.capture().lock().unwrap();
+    assert_eq!(requests.len(), 2);
+    assert_eq!(requests[0].method, "GET");
+    assert_eq!(requests[0].target, "/");
+    assert_eq!(requests[0].raw, first);
+    let names = requests[0]
+        .headers
+        .iter()
+        .map(|header| header.name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["Host", "User-Agent"]);
+    assert_eq!(requests[1].method, "POST");
+    assert_eq!(
+        requests[1].raw,
+        "POST /form HTTP/1.1\nhost: localhost\ncontent-length: 5\n\n"
+    );
+}
+
+#[tokio::test]
+async fn http2_inspector_drops_oversized_header_blocks() {
+    let mut data = b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n".to_vec();
+    data.extend(frame(0x4, 0, 0, &[]));
+    // A header block for stream 1 spread over CONTINUATION frames well past
+    // the size limit.
+    data.extend(frame(0x1, 0x1, 1, &[0x82; 4096]));
+    for i in 0..32 {
+        let flags = if i == 31 { 0x4 } else { 0 };
+        data.extend(frame(0x9, flags, 1, &[0x82; 4096]));
+    }
+    // :method GET, :scheme https, :path /
+    data.extend(frame(0x1, 0x5, 3, &[0x82, 0x87, 0x84]));
+
+    let inspector = read_through(&data, Http2Inspector::new).await;
+
+    let frames = inspector.capture().lock().unwrap();
+    let frame_types = frames
+        .preface
+        .iter()
+        .map(|frame| match frame {
+            Frame::Settings(_) => "SETTINGS".to_string(),
+            Frame::Headers(headers) => format!("HEADERS {}", headers.stream_id),
+            frame => format!("{frame:?}"),
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(frame_types, ["SETTINGS", "HEADERS 3"]);
+}
+
+#[tokio::test]
+async fn http2_inspector_skips_reset_streams() {
+    let mut data = b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n".to_vec();
+    data.extend(frame(0x4, 0, 0, &[]));
+    // Streams 1, 3 and 5 for GET / and stream 7 for GET /a, of which the
+    // client resets stream 1.
+    for stream_id in [1, 3, 5] {
+        data.extend(frame(0x1, 0x5, stream_id, &[0x82, 0x87, 0x84]));
+    }
+    data.extend(frame(0x1, 0x5, 7, &[0x82, 0x87, 0x04, 2, b'/', b'a']));
+    data.extend(frame(0x3, 0, 1, &[0, 0, 0, 8]));
+
+    let (mut client, server) = duplex(1024);
+    client.write_all(&data).await.unwrap();
+    let mut inspector = Http2Inspector::new(server);
+    let mut received = vec![0; data.len()];
+    inspector.read_exact(&mut received).await.unwrap();
+    // The server refuses stream 3, in a write split within a frame header.
+    let mut sent = frame(0x4, 0, 0, &[0, 3, 0, 0, 0, 100]);
+    sent.extend(frame(0x3, 0, 3, &[0, 0, 0, 7]));
+    inspector.write_all(&sent[..20]).await.unwrap();
+    inspector.write_all(&sent[20..]).await.unwrap();
+
+    let mut frames = inspector.capture().lock().unwrap();
+    let mut take = |path| frames.take_request("GET", path).map(|r| r.stream_id);
+    assert_eq!(take("/a"), Some(7));
+    assert_eq!(take("/"), Some(5));
+    assert_eq!(take("/"), None);
+}